@@ -0,0 +1,24 @@
+use std::{env, fs, path::Path};
+
+/// The least squares fit of the RGB to spectrum table.
+#[path = "src/spectrum/fit.rs"]
+mod fit;
+
+/// The spectral model shared with `spectrum`.
+#[path = "src/spectrum/model.rs"]
+mod model;
+
+/// Fit the RGB to spectrum table ahead of time, so that spectral renders do not wait for it.
+/// The coefficients are written as little-endian floats and included by `spectrum`.
+fn main() {
+    println!("cargo::rerun-if-changed=src/spectrum/fit.rs");
+    println!("cargo::rerun-if-changed=src/spectrum/model.rs");
+
+    let bytes: Vec<u8> = fit::fit()
+        .iter()
+        .flat_map(|c| c.to_array())
+        .flat_map(f32::to_le_bytes)
+        .collect();
+    let out_dir = env::var_os("OUT_DIR").unwrap();
+    fs::write(Path::new(&out_dir).join("rgb_to_spectrum.bin"), bytes).unwrap();
+}
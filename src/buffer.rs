@@ -1,6 +1,6 @@
 use image::{ImageBuffer, RgbImage};
 
-use crate::color::{Color, color_bytes};
+use crate::color::{self, Color, color_bytes};
 
 /// A buffer to store the result of path tracing.
 pub struct Buffer {
@@ -10,30 +10,57 @@ pub struct Buffer {
     /// The height of image.
     height: u32,
 
-    /// The sample colors of image.
-    samples: Vec<Vec<Color>>,
+    /// The accumulated sample colors of each pixel.
+    samples: Vec<Color>,
+
+    /// The number of samples accumulated into each pixel.
+    counts: Vec<u32>,
 }
 
 impl Buffer {
     /// Create a empty buffer with width and height.
-    pub const fn new(width: u32, height: u32) -> Self {
+    pub fn new(width: u32, height: u32) -> Self {
+        let size = (width * height) as usize;
         Self {
             width,
             height,
-            samples: Vec::new(),
+            samples: vec![Color::default(); size],
+            counts: vec![0; size],
         }
     }
 
-    /// Push a color into the buffer.
+    /// Push a color sample into the buffer.
     pub fn push(&mut self, x: u32, y: u32, color: Color) {
         assert!(x < self.width && y < self.height, "Invalid pixel location!");
         let index = (y * self.width + x) as usize;
-        self.samples[index].push(color);
+        self.samples[index] += color;
+        self.counts[index] += 1;
+    }
+
+    /// Push one color sample for every pixel of the row `y`.
+    pub fn push_row(&mut self, y: u32, colors: &[Color]) {
+        assert!(
+            y < self.height && colors.len() == self.width as usize,
+            "Invalid row!"
+        );
+        for (x, color) in colors.iter().enumerate() {
+            self.push(x as u32, y, *color);
+        }
     }
 
-    /// Extend a list of colors into the buffer.
-    pub fn extend(&mut self, colors: Vec<Vec<Color>>) {
-        self.samples.extend(colors);
+    /// Get the number of samples accumulated into the pixel.
+    pub fn count(&self, x: u32, y: u32) -> u32 {
+        self.counts[(y * self.width + x) as usize]
+    }
+
+    /// Get the color of the pixel averaged by its own number of samples. Pixels without any
+    /// sample are black.
+    pub fn color(&self, x: u32, y: u32) -> Color {
+        let index = (y * self.width + x) as usize;
+        match self.counts[index] {
+            0 => color::BLACK,
+            n => self.samples[index] / n as f32,
+        }
     }
 
     /// Transite the buffer into rgb image.
@@ -41,8 +68,7 @@ impl Buffer {
         let mut buf = Vec::new();
         for y in 0..self.height {
             for x in 0..self.width {
-                let color = self.samples[y as usize][x as usize];
-                let [r, g, b] = color_bytes(color);
+                let [r, g, b] = color_bytes(self.color(x, y));
                 buf.push(r);
                 buf.push(g);
                 buf.push(b);
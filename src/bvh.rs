@@ -144,4 +144,12 @@ impl Bounded for BvhNode {
             Self::Node { bbox, .. } => *bbox,
         }
     }
+
+    /// Get total surface area of objects in this node.
+    fn area(&self) -> f32 {
+        match self {
+            Self::Leaf { object, .. } => object.area(),
+            Self::Node { left, right, .. } => left.area() + right.area(),
+        }
+    }
 }
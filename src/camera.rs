@@ -1,6 +1,6 @@
 use crate::{
     math::random,
-    math::{Point3, Ray, Vec3, vec3::random_in_unit_disk},
+    math::{Point3, Ray, RayDifferentials, Vec3, vec3::random_in_unit_disk},
 };
 
 #[allow(non_snake_case)]
@@ -75,16 +75,20 @@ impl Camera {
     }
 
     /// Get the ray from aperture to pixel plane.
-    /// The pixel plane uses coordinate (i, j) which ranged between [0, 1).
-    pub fn get_ray(&self, i: f32, j: f32) -> Ray {
+    /// The pixel plane uses coordinate (i, j) which ranged between [0, 1), and `di` and `dj` are
+    /// the offsets to the neighbouring pixels which give the differentials of the ray.
+    pub fn get_ray(&self, i: f32, j: f32, di: f32, dj: f32) -> Ray {
         let mut lens_offset = self.lens_radius * random_in_unit_disk();
         lens_offset = self.c_x * lens_offset.x + self.c_y * lens_offset.y;
         let shutter_time = random();
-        Ray::new(
-            self.origin + lens_offset,
-            self.upper_left + i * self.u + j * self.v - self.origin - lens_offset,
-            shutter_time,
-        )
+        let origin = self.origin + lens_offset;
+        let direction = self.upper_left + i * self.u + j * self.v - origin;
+        Ray::new(origin, direction, shutter_time).with_differentials(RayDifferentials {
+            rx_ori: origin,
+            rx_dir: direction + di * self.u,
+            ry_ori: origin,
+            ry_dir: direction + dj * self.v,
+        })
     }
 
     /// Get how much width for one pixel.
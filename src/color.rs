@@ -1,5 +1,7 @@
 use glam::Vec3A;
 
+pub use crate::spectrum::xyz_to_rgb;
+
 /// Struct member `x`, `y`, `z` respectively represent red, green, blue component in a color.
 pub type Color = Vec3A;
 
@@ -18,3 +20,8 @@ pub fn color_bytes(color: Color) -> [u8; 3] {
         (256.0 * color.z.clamp(0.0, 0.999).powf(1.0 / SRGB_GAMMA)) as u8,
     ]
 }
+
+/// Get the relative luminance of a linear color.
+pub fn luminance(color: Color) -> f32 {
+    color.dot(Color::new(0.2126, 0.7152, 0.0722))
+}
@@ -0,0 +1,296 @@
+/// A piecewise-constant 1D distribution over [0, 1) used for importance sampling.
+pub struct Distribution1D {
+    /// The non-negative function values of each segment.
+    func: Vec<f32>,
+
+    /// The cumulative distribution function which has one more element than `func`.
+    cdf: Vec<f32>,
+
+    /// The integral of the function over [0, 1).
+    integral: f32,
+}
+
+impl Distribution1D {
+    /// Create a distribution from the function values of each equally sized segment.
+    pub fn new(func: Vec<f32>) -> Self {
+        let n = func.len();
+        let mut cdf = vec![0.0; n + 1];
+        for i in 1..=n {
+            cdf[i] = cdf[i - 1] + func[i - 1].max(0.0) / n as f32;
+        }
+        let integral = cdf[n];
+        for (i, c) in cdf.iter_mut().enumerate().skip(1) {
+            // Fall back to uniform sampling if the function is zero everywhere
+            *c = if integral > 0.0 {
+                *c / integral
+            } else {
+                i as f32 / n as f32
+            };
+        }
+        Self {
+            func,
+            cdf,
+            integral,
+        }
+    }
+
+    /// Return the number of segments.
+    pub fn count(&self) -> usize {
+        self.func.len()
+    }
+
+    /// Return the integral of the function over [0, 1).
+    pub const fn integral(&self) -> f32 {
+        self.integral
+    }
+
+    /// Map an uniform random number `u` to the distribution. Return the sampled value in [0, 1),
+    /// its probability density and the index of the segment it falls in.
+    pub fn sample(&self, u: f32) -> (f32, f32, usize) {
+        let n = self.count();
+        let offset = self
+            .cdf
+            .partition_point(|&c| c <= u)
+            .saturating_sub(1)
+            .min(n - 1);
+        let width = self.cdf[offset + 1] - self.cdf[offset];
+        let du = if width > 0.0 {
+            (u - self.cdf[offset]) / width
+        } else {
+            0.0
+        };
+        let x = ((offset as f32 + du) / n as f32).min(1.0 - f32::EPSILON);
+        (x, self.pdf_at(offset), offset)
+    }
+
+    /// Return the probability density of value `x` in [0, 1).
+    pub fn pdf(&self, x: f32) -> f32 {
+        let n = self.count();
+        let offset = ((x * n as f32) as usize).min(n - 1);
+        self.pdf_at(offset)
+    }
+
+    /// Return the probability density of the segment in the `offset` index.
+    fn pdf_at(&self, offset: usize) -> f32 {
+        if self.integral > 0.0 {
+            self.func[offset].max(0.0) / self.integral
+        } else {
+            1.0
+        }
+    }
+}
+
+/// A piecewise-constant 2D distribution over [0, 1)^2 which samples `v` from the marginal
+/// distribution and then `u` from the conditional distribution of that row.
+pub struct Distribution2D {
+    /// The conditional distributions of `u` in each row.
+    conditional: Vec<Distribution1D>,
+
+    /// The marginal distribution of `v`.
+    marginal: Distribution1D,
+}
+
+impl Distribution2D {
+    /// Create a distribution from row-major function values with `nu` columns and `nv` rows.
+    pub fn new(func: &[f32], nu: usize, nv: usize) -> Self {
+        assert!(
+            nu > 0 && nv > 0 && func.len() == nu * nv,
+            "Invalid distribution size!"
+        );
+        let conditional: Vec<_> = func
+            .chunks(nu)
+            .map(|row| Distribution1D::new(row.to_vec()))
+            .collect();
+        let marginal = Distribution1D::new(conditional.iter().map(|d| d.integral()).collect());
+        Self {
+            conditional,
+            marginal,
+        }
+    }
+
+    /// Map two uniform random numbers to the distribution. Return the sampled `(u, v)` and its
+    /// probability density.
+    pub fn sample(&self, u1: f32, u2: f32) -> ((f32, f32), f32) {
+        let (v, pdf_v, row) = self.marginal.sample(u2);
+        let (u, pdf_u, _) = self.conditional[row].sample(u1);
+        ((u, v), pdf_u * pdf_v)
+    }
+
+    /// Return the integral of the function over [0, 1)^2.
+    pub fn integral(&self) -> f32 {
+        self.marginal.integral()
+    }
+
+    /// Return the probability density of `(u, v)`.
+    pub fn pdf(&self, u: f32, v: f32) -> f32 {
+        let nv = self.conditional.len();
+        let row = ((v * nv as f32) as usize).min(nv - 1);
+        self.conditional[row].pdf(u) * self.marginal.pdf(v)
+    }
+}
+
+/// An alias table which samples one of many discrete items in proportion to their weights in
+/// constant time.
+pub struct AliasTable {
+    /// The probability of keeping the item of each bin instead of its alias.
+    threshold: Vec<f32>,
+
+    /// The item which takes the rest of each bin.
+    alias: Vec<usize>,
+
+    /// The probability of each item.
+    pmf: Vec<f32>,
+}
+
+impl AliasTable {
+    /// Create an alias table from non-negative weights. Items are chosen uniformly if all weights
+    /// are zero.
+    pub fn new(weights: &[f32]) -> Self {
+        let n = weights.len();
+        let total: f32 = weights.iter().map(|w| w.max(0.0)).sum();
+        let pmf: Vec<f32> = if total > 0.0 {
+            weights.iter().map(|w| w.max(0.0) / total).collect()
+        } else {
+            vec![1.0 / n as f32; n]
+        };
+
+        // Split the items into bins which are under and over full after scaled by n.
+        let mut scaled: Vec<f32> = pmf.iter().map(|p| p * n as f32).collect();
+        let (mut small, mut large): (Vec<usize>, Vec<usize>) =
+            (0..n).partition(|&i| scaled[i] < 1.0);
+        let mut threshold = vec![1.0; n];
+        let mut alias: Vec<usize> = (0..n).collect();
+        while let (Some(s), Some(&l)) = (small.pop(), large.last()) {
+            threshold[s] = scaled[s];
+            alias[s] = l;
+            scaled[l] -= 1.0 - scaled[s];
+            if scaled[l] < 1.0 {
+                large.pop();
+                small.push(l);
+            }
+        }
+
+        Self {
+            threshold,
+            alias,
+            pmf,
+        }
+    }
+
+    /// Return the number of items.
+    pub fn count(&self) -> usize {
+        self.pmf.len()
+    }
+
+    /// Map an uniform random number `u` to an item. Return its index and probability.
+    pub fn sample(&self, u: f32) -> (usize, f32) {
+        let n = self.count();
+        let scaled = u * n as f32;
+        let bin = (scaled as usize).min(n - 1);
+        let index = if scaled - (bin as f32) < self.threshold[bin] {
+            bin
+        } else {
+            self.alias[bin]
+        };
+        (index, self.pmf[index])
+    }
+
+    /// Return the probability of choosing the item in `index`.
+    pub fn pmf(&self, index: usize) -> f32 {
+        self.pmf[index]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The number of stratified uniform numbers used to check the sampling.
+    const N: usize = 100_000;
+
+    /// Generate stratified uniform numbers in [0, 1).
+    fn stratified() -> impl DoubleEndedIterator<Item = f32> {
+        (0..N).map(|i| (i as f32 + 0.5) / N as f32)
+    }
+
+    #[test]
+    fn distribution_1d_pdf_is_normalized() {
+        let distribution = Distribution1D::new(vec![1.0, 3.0, 0.0, 4.0]);
+        assert!((distribution.integral() - 2.0).abs() < 1e-6);
+        let integral: f32 = stratified().map(|x| distribution.pdf(x)).sum::<f32>() / N as f32;
+        assert!((integral - 1.0).abs() < 1e-3);
+
+        // A function which is zero everywhere is sampled uniformly.
+        let uniform = Distribution1D::new(vec![0.0; 4]);
+        assert!(stratified().all(|x| uniform.pdf(x) == 1.0));
+    }
+
+    #[test]
+    fn distribution_1d_sample_agrees_with_pdf() {
+        let func = vec![1.0, 3.0, 0.0, 4.0];
+        let distribution = Distribution1D::new(func.clone());
+        let mut counts = [0usize; 4];
+        for u in stratified() {
+            let (x, pdf, offset) = distribution.sample(u);
+            assert!((0.0..1.0).contains(&x));
+            assert_eq!(offset, (x * 4.0) as usize);
+            assert_eq!(pdf, distribution.pdf(x));
+            counts[offset] += 1;
+        }
+        for (count, f) in counts.iter().zip(&func) {
+            assert!((*count as f32 / N as f32 - f / 8.0).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn distribution_2d_pdf_is_normalized() {
+        let func = [1.0, 2.0, 0.0, 5.0, 0.0, 0.0, 3.0, 1.0, 4.0];
+        let distribution = Distribution2D::new(&func, 3, 3);
+        assert!((distribution.integral() - 16.0 / 9.0).abs() < 1e-6);
+        let n = 300;
+        let integral: f32 = (0..n * n)
+            .map(|i| {
+                let u = ((i % n) as f32 + 0.5) / n as f32;
+                let v = ((i / n) as f32 + 0.5) / n as f32;
+                distribution.pdf(u, v)
+            })
+            .sum::<f32>()
+            / (n * n) as f32;
+        assert!((integral - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn distribution_2d_sample_agrees_with_pdf() {
+        let func = [1.0, 2.0, 0.0, 5.0, 0.0, 0.0, 3.0, 1.0, 4.0];
+        let distribution = Distribution2D::new(&func, 3, 3);
+        for (u1, u2) in stratified().zip(stratified().rev()) {
+            let ((u, v), pdf) = distribution.sample(u1, u2);
+            assert!(pdf > 0.0);
+            assert!((pdf - distribution.pdf(u, v)).abs() < 1e-4 * pdf);
+        }
+    }
+
+    #[test]
+    fn alias_table_matches_weights() {
+        let weights = [1.0, 0.0, 5.0, 2.0, 0.5, 1.5];
+        let table = AliasTable::new(&weights);
+        let total: f32 = weights.iter().sum();
+        for (i, w) in weights.iter().enumerate() {
+            assert!((table.pmf(i) - w / total).abs() < 1e-6);
+        }
+
+        let mut counts = [0usize; 6];
+        for u in stratified() {
+            let (index, pmf) = table.sample(u);
+            assert_eq!(pmf, table.pmf(index));
+            counts[index] += 1;
+        }
+        for (i, count) in counts.iter().enumerate() {
+            assert!((*count as f32 / N as f32 - table.pmf(i)).abs() < 1e-3);
+        }
+
+        // Items are chosen uniformly if all weights are zero.
+        let uniform = AliasTable::new(&[0.0; 4]);
+        assert!((0..4).all(|i| uniform.pmf(i) == 0.25));
+    }
+}
@@ -0,0 +1,101 @@
+use std::f32::consts::PI;
+
+use crate::{
+    color::{Color, luminance},
+    distribution::Distribution2D,
+    math::{Vec3, random},
+    shape::sphere::Sphere,
+};
+
+pub mod env_map;
+pub mod sky;
+
+pub trait Environment: Send + Sync {
+    /// Get the radiance arriving from the environment along the direction `dir`.
+    fn emit(&self, dir: Vec3) -> Color;
+
+    /// Sample a direction towards the environment. Return `None` if the environment can not be
+    /// importance sampled.
+    fn sample(&self) -> Option<Vec3> {
+        None
+    }
+
+    /// Return the probability density in solid angle of sampling the direction `dir`.
+    fn pdf(&self, _dir: Vec3) -> f32 {
+        0.0
+    }
+
+    /// Estimate the total power falling on a disk with the radius `scene_radius` of the scene
+    /// bounds, which is used to choose between light sources. Zero for environments which can
+    /// not be importance sampled.
+    fn power(&self, _scene_radius: f32) -> f32 {
+        0.0
+    }
+}
+
+/// Transform the polar angle `theta` measured from -Y axis and the azimuth angle `phi` into a
+/// unit direction. The inverse of `Sphere::get_sphere_uv`.
+pub fn spherical_direction(theta: f32, phi: f32) -> Vec3 {
+    let (sin_theta, cos_theta) = theta.sin_cos();
+    let (sin_phi, cos_phi) = (phi - PI).sin_cos();
+    Vec3::new(sin_theta * cos_phi, -cos_theta, -sin_theta * sin_phi)
+}
+
+/// Transform the direction into equirectangular coordinates in [0, 1) with the row counted from
+/// the top, where the top row looks towards +Y axis.
+pub fn lat_long_uv(dir: Vec3) -> (f32, f32) {
+    let (u, v) = Sphere::get_sphere_uv(dir);
+    (u, 1.0 - v)
+}
+
+/// A distribution of directions in proportion to the luminance of an equirectangular map.
+pub struct LatLongDistribution {
+    /// The distribution of pixels weighted by the solid angle they cover.
+    distribution: Distribution2D,
+}
+
+impl LatLongDistribution {
+    /// Create a distribution of a `width` x `height` map whose pixel colors are given by `color`.
+    pub fn new<F>(width: u32, height: u32, color: F) -> Self
+    where
+        F: Fn(u32, u32) -> Color,
+    {
+        // Weight each pixel by the solid angle it covers which shrinks towards the poles.
+        let mut func = Vec::with_capacity((width * height) as usize);
+        for j in 0..height {
+            let sin_theta = (PI * (j as f32 + 0.5) / height as f32).sin();
+            for i in 0..width {
+                func.push(luminance(color(i, j)).max(0.0) * sin_theta);
+            }
+        }
+        Self {
+            distribution: Distribution2D::new(&func, width as usize, height as usize),
+        }
+    }
+
+    /// Sample a unit direction. Return `None` if the map is black everywhere.
+    pub fn sample(&self) -> Option<Vec3> {
+        let ((u, v), pdf) = self.distribution.sample(random(), random());
+        if pdf <= 0.0 {
+            return None;
+        }
+        Some(spherical_direction((1.0 - v) * PI, u * 2.0 * PI))
+    }
+
+    /// Return the integral of the luminance over all directions.
+    pub fn integral(&self) -> f32 {
+        // Convert the integral from image area to solid angle.
+        2.0 * PI * PI * self.distribution.integral()
+    }
+
+    /// Return the probability density in solid angle of sampling the unit direction `dir`.
+    pub fn pdf(&self, dir: Vec3) -> f32 {
+        let (u, v) = lat_long_uv(dir);
+        let sin_theta = (v * PI).sin();
+        if sin_theta <= 0.0 {
+            return 0.0;
+        }
+        // Convert the density from image area to solid angle.
+        self.distribution.pdf(u, v) / (2.0 * PI * PI * sin_theta)
+    }
+}
@@ -0,0 +1,93 @@
+use std::f32::consts::PI;
+use std::path::Path;
+use std::slice;
+
+use glam::{Mat3A, Vec2};
+use image::ImageError;
+use image::error::{DecodingError, ImageFormatHint};
+
+use crate::{
+    color::Color,
+    environment::{Environment, LatLongDistribution, lat_long_uv},
+    math::Vec3,
+    texture::image_texture::{Filter, RtwImage, Sampling, Wrap},
+};
+
+/// An environment light from an equirectangular image which is importance sampled by luminance.
+pub struct EnvironmentMap {
+    /// The decoded image of the environment.
+    image: RtwImage,
+
+    /// The distribution of directions in proportion to the luminance.
+    distribution: LatLongDistribution,
+
+    /// The rotation which transforms directions from map space to world space.
+    rotation: Mat3A,
+
+    /// The scale of the emitted radiance.
+    intensity: f32,
+}
+
+impl EnvironmentMap {
+    /// Load the environment from an equirectangular image such as HDR or EXR. Fail if the image
+    /// is empty.
+    pub fn load<P>(path: P) -> image::ImageResult<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let image = RtwImage::from_path(path)?;
+        if image.width == 0 || image.height == 0 {
+            return Err(ImageError::Decoding(DecodingError::new(
+                ImageFormatHint::Unknown,
+                "empty environment map",
+            )));
+        }
+        let distribution =
+            LatLongDistribution::new(image.width, image.height, |i, j| image.pixel(i, j));
+        Ok(Self {
+            image,
+            distribution,
+            rotation: Mat3A::IDENTITY,
+            intensity: 1.0,
+        })
+    }
+
+    /// Set the rotation around y-axis in radians.
+    pub fn rotation(mut self, angle: f32) -> Self {
+        self.rotation = Mat3A::from_rotation_y(angle);
+        self
+    }
+
+    /// Set the scale of the emitted radiance.
+    pub const fn intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+}
+
+impl Environment for EnvironmentMap {
+    fn emit(&self, dir: Vec3) -> Color {
+        // Interpolate like image textures, wrapping around the seam of the longitude.
+        let sampling = Sampling {
+            filter: Filter::Bilinear,
+            wrap_u: Wrap::Repeat,
+            ..Default::default()
+        };
+        let (u, v) = lat_long_uv(self.rotation.transpose() * dir);
+        let images = slice::from_ref(&self.image);
+        let color = sampling.lookup(images, u, 1.0 - v, Vec2::ZERO, Vec2::ZERO, RtwImage::pixel);
+        self.intensity * color
+    }
+
+    fn sample(&self) -> Option<Vec3> {
+        self.distribution.sample().map(|dir| self.rotation * dir)
+    }
+
+    fn pdf(&self, dir: Vec3) -> f32 {
+        self.distribution.pdf(self.rotation.transpose() * dir)
+    }
+
+    fn power(&self, scene_radius: f32) -> f32 {
+        PI * scene_radius * scene_radius * self.intensity * self.distribution.integral()
+    }
+}
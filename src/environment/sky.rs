@@ -0,0 +1,276 @@
+use std::f32::consts::PI;
+
+use crate::{
+    color::{self, Color, luminance},
+    environment::{Environment, LatLongDistribution, spherical_direction},
+    math::{Vec3, random, vec3::random_in_cone},
+    onb::ONB,
+};
+
+/// The luminance of the sun outside the atmosphere in kcd/m^2.
+const SUN_LUMINANCE: f32 = 2.0e6;
+
+/// The resolution of the table used to importance sample the sky dome.
+const TABLE_WIDTH: u32 = 128;
+const TABLE_HEIGHT: u32 = 64;
+
+/// An analytic daylight sky from the Preetham model together with a sun disk. The radiance is in
+/// kcd/m^2 scaled by `intensity`, and +Y axis is the zenith.
+pub struct Sky {
+    /// The unit direction towards the sun.
+    sun_dir: Vec3,
+
+    /// The haziness of the atmosphere which ranges in [2, 10].
+    turbidity: f32,
+
+    /// The albedo of the ground below the horizon.
+    ground_albedo: Color,
+
+    /// The scale of the emitted radiance.
+    intensity: f32,
+
+    /// The angular radius of the sun disk in radians.
+    sun_radius: f32,
+
+    /// The zenith value of luminance `Y` and chromaticity `x` and `y`.
+    zenith: [f32; 3],
+
+    /// The Perez coefficients `A` to `E` of luminance `Y` and chromaticity `x` and `y`.
+    perez: [[f32; 5]; 3],
+
+    /// The radiance of the sun disk after the atmospheric attenuation.
+    sun_radiance: Color,
+
+    /// The radiance of the ground lit by the sky and the sun.
+    ground_radiance: Color,
+
+    /// The distribution of directions of the sky dome without the sun.
+    distribution: LatLongDistribution,
+}
+
+impl Sky {
+    /// Create a sky from the elevation of the sun above the horizon and its azimuth measured from
+    /// +Z axis towards +X axis, both in radians.
+    pub fn new(elevation: f32, azimuth: f32) -> Self {
+        Self {
+            sun_dir: Vec3::Y,
+            turbidity: 3.0,
+            ground_albedo: Color::splat(0.3),
+            intensity: 0.05,
+            sun_radius: 0.00465,
+            zenith: [0.0; 3],
+            perez: [[0.0; 5]; 3],
+            sun_radiance: color::BLACK,
+            ground_radiance: color::BLACK,
+            distribution: LatLongDistribution::new(1, 1, |_, _| color::BLACK),
+        }
+        .sun(elevation, azimuth)
+    }
+
+    /// Set the elevation and the azimuth of the sun in radians.
+    pub fn sun(mut self, elevation: f32, azimuth: f32) -> Self {
+        let (sin_el, cos_el) = elevation.sin_cos();
+        let (sin_az, cos_az) = azimuth.sin_cos();
+        self.sun_dir = Vec3::new(cos_el * sin_az, sin_el, cos_el * cos_az);
+        self.update()
+    }
+
+    /// Set the turbidity of the atmosphere which ranges in [2, 10].
+    pub fn turbidity(mut self, turbidity: f32) -> Self {
+        self.turbidity = turbidity.clamp(2.0, 10.0);
+        self.update()
+    }
+
+    /// Set the albedo of the ground below the horizon.
+    pub fn ground_albedo(mut self, albedo: Color) -> Self {
+        self.ground_albedo = albedo;
+        self.update()
+    }
+
+    /// Set the scale of the emitted radiance.
+    pub fn intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self.update()
+    }
+
+    /// Set the angular radius of the sun disk in radians.
+    pub fn sun_radius(mut self, radius: f32) -> Self {
+        self.sun_radius = radius;
+        self.update()
+    }
+
+    /// Whether the sun is above the horizon.
+    fn sun_visible(&self) -> bool {
+        self.sun_dir.y > 0.0
+    }
+
+    /// Return the cosine of the angular radius of the sun disk.
+    fn cos_sun_radius(&self) -> f32 {
+        self.sun_radius.cos()
+    }
+
+    /// Recompute the model coefficients and the sampling table after a parameter changed.
+    fn update(mut self) -> Self {
+        let t = self.turbidity;
+        // The Preetham model is only valid for the sun above the horizon.
+        let theta_s = self.sun_dir.y.clamp(0.0, 1.0).acos();
+
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta_s);
+        let zenith_y = (4.0453 * t - 4.9710).mul_add(chi.tan(), -0.2155 * t) + 2.4192;
+        let chromaticity = |c: [[f32; 4]; 3]| {
+            let poly = |k: [f32; 4]| {
+                k[0] * theta_s.powi(3) + k[1] * theta_s.powi(2) + k[2] * theta_s + k[3]
+            };
+            t * t * poly(c[0]) + t * poly(c[1]) + poly(c[2])
+        };
+        let zenith_x = chromaticity([
+            [0.00166, -0.00375, 0.00209, 0.0],
+            [-0.02903, 0.06377, -0.03202, 0.00394],
+            [0.11693, -0.21196, 0.06052, 0.25886],
+        ]);
+        let zenith_yc = chromaticity([
+            [0.00275, -0.00610, 0.00317, 0.0],
+            [-0.04214, 0.08970, -0.04153, 0.00516],
+            [0.15346, -0.26756, 0.06670, 0.26688],
+        ]);
+        self.zenith = [zenith_y, zenith_x, zenith_yc];
+        self.perez = [
+            [
+                0.1787 * t - 1.4630,
+                -0.3554 * t + 0.4275,
+                -0.0227 * t + 5.3251,
+                0.1206 * t - 2.5771,
+                -0.0670 * t + 0.3703,
+            ],
+            [
+                -0.0193 * t - 0.2592,
+                -0.0665 * t + 0.0008,
+                -0.0004 * t + 0.2125,
+                -0.0641 * t - 0.8989,
+                -0.0033 * t + 0.0452,
+            ],
+            [
+                -0.0167 * t - 0.2608,
+                -0.0950 * t + 0.0092,
+                -0.0079 * t + 0.2102,
+                -0.0441 * t - 1.6537,
+                -0.0109 * t + 0.0529,
+            ],
+        ];
+
+        // Attenuate the sun by Rayleigh and aerosol scattering along the relative optical mass,
+        // using 650, 550 and 450 nm as the wavelengths of red, green and blue.
+        self.sun_radiance = if self.sun_visible() {
+            let theta_deg = theta_s.to_degrees();
+            let mass = 1.0 / (theta_s.cos() + 0.15 * (93.885 - theta_deg).powf(-1.253));
+            let beta = 0.04608 * t - 0.04586;
+            let transmittance = |lambda: f32| {
+                let rayleigh = (-0.008735 * lambda.powf(-4.08) * mass).exp();
+                let aerosol = (-beta * lambda.powf(-1.3) * mass).exp();
+                rayleigh * aerosol
+            };
+            SUN_LUMINANCE
+                * self.intensity
+                * Color::new(
+                    transmittance(0.65),
+                    transmittance(0.55),
+                    transmittance(0.45),
+                )
+        } else {
+            color::BLACK
+        };
+
+        // Light the ground by the irradiance from the sky dome and the sun.
+        let pixel_dir = |i: u32, j: u32| {
+            let theta = PI * (1.0 - (j as f32 + 0.5) / TABLE_HEIGHT as f32);
+            let phi = 2.0 * PI * (i as f32 + 0.5) / TABLE_WIDTH as f32;
+            spherical_direction(theta, phi)
+        };
+        let pixel_solid_angle = 2.0 * PI * PI / (TABLE_WIDTH * TABLE_HEIGHT) as f32;
+        let mut irradiance = color::BLACK;
+        for j in 0..TABLE_HEIGHT / 2 {
+            for i in 0..TABLE_WIDTH {
+                let dir = pixel_dir(i, j);
+                let sin_theta = (1.0 - dir.y * dir.y).max(0.0).sqrt();
+                irradiance += self.sky_radiance(dir) * dir.y * sin_theta * pixel_solid_angle;
+            }
+        }
+        let sun_solid_angle = 2.0 * PI * (1.0 - self.cos_sun_radius());
+        irradiance += self.sun_radiance * sun_solid_angle * self.sun_dir.y.max(0.0);
+        self.ground_radiance = self.ground_albedo * irradiance / PI;
+
+        self.distribution = LatLongDistribution::new(TABLE_WIDTH, TABLE_HEIGHT, |i, j| {
+            self.sky_radiance(pixel_dir(i, j))
+        });
+        self
+    }
+
+    /// Evaluate the Perez distribution for the view angle `theta` from the zenith and the angle
+    /// `gamma` from the sun.
+    fn perez(c: [f32; 5], cos_theta: f32, gamma: f32) -> f32 {
+        let [a, b, c, d, e] = c;
+        (1.0 + a * (b / cos_theta.max(1e-3)).exp())
+            * (1.0 + c * (d * gamma).exp() + e * gamma.cos().powi(2))
+    }
+
+    /// Get the radiance of the sky dome or the ground without the sun disk.
+    fn sky_radiance(&self, dir: Vec3) -> Color {
+        if dir.y < 0.0 {
+            return self.ground_radiance;
+        }
+        let cos_theta_s = self.sun_dir.y.clamp(0.0, 1.0);
+        let theta_s = cos_theta_s.acos();
+        let gamma = dir.dot(self.sun_dir).clamp(-1.0, 1.0).acos();
+        let [luma, x, y] = [0, 1, 2].map(|k| {
+            self.zenith[k] * Self::perez(self.perez[k], dir.y, gamma)
+                / Self::perez(self.perez[k], 1.0, theta_s)
+        });
+        let xyz = Vec3::new(x * luma / y, luma, (1.0 - x - y) * luma / y);
+        self.intensity * color::xyz_to_rgb(xyz).max(color::BLACK)
+    }
+
+    /// Return the probability of sampling the sun instead of the sky dome.
+    fn sun_probability(&self) -> f32 {
+        if self.sun_visible() { 0.5 } else { 0.0 }
+    }
+}
+
+impl Environment for Sky {
+    fn emit(&self, dir: Vec3) -> Color {
+        let sky = self.sky_radiance(dir);
+        if self.sun_visible() && dir.dot(self.sun_dir) >= self.cos_sun_radius() {
+            sky + self.sun_radiance
+        } else {
+            sky
+        }
+    }
+
+    fn sample(&self) -> Option<Vec3> {
+        if random() < self.sun_probability() {
+            let uvw = ONB::new(self.sun_dir);
+            Some(uvw.transform(random_in_cone(self.cos_sun_radius())))
+        } else {
+            self.distribution.sample()
+        }
+    }
+
+    fn pdf(&self, dir: Vec3) -> f32 {
+        let p_sun = self.sun_probability();
+        let cos_max = self.cos_sun_radius();
+        let sun_pdf = if dir.dot(self.sun_dir) >= cos_max {
+            1.0 / (2.0 * PI * (1.0 - cos_max))
+        } else {
+            0.0
+        };
+        p_sun * sun_pdf + (1.0 - p_sun) * self.distribution.pdf(dir)
+    }
+
+    fn power(&self, scene_radius: f32) -> f32 {
+        let sun = if self.sun_visible() {
+            2.0 * PI * (1.0 - self.cos_sun_radius()) * luminance(self.sun_radiance)
+        } else {
+            0.0
+        };
+        PI * scene_radius * scene_radius * (self.distribution.integral() + sun)
+    }
+}
@@ -3,7 +3,10 @@ pub mod buffer;
 pub mod bvh;
 pub mod camera;
 pub mod color;
+pub mod distribution;
+pub mod environment;
 pub mod interval;
+pub mod light;
 pub mod material;
 pub mod math;
 pub mod object;
@@ -11,4 +14,5 @@ pub mod onb;
 pub mod renderer;
 pub mod scene;
 pub mod shape;
+pub mod spectrum;
 pub mod texture;
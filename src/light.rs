@@ -0,0 +1,128 @@
+use std::f32::consts::PI;
+
+use serde::Deserialize;
+
+use crate::{
+    color::Color,
+    math::{Point3, Vec3},
+};
+
+pub mod directional;
+pub mod ies;
+pub mod point;
+pub mod sampler;
+pub mod spot;
+
+pub use directional::DirectionalLight;
+pub use ies::IesLight;
+pub use point::PointLight;
+pub use spot::SpotLight;
+
+/// The light arriving at a shading point from a light source.
+pub struct LightSample {
+    /// The unit direction from the shading point towards the light.
+    pub dir: Vec3,
+
+    /// The distance from the shading point to the light, which is infinite for distant lights.
+    pub distance: f32,
+
+    /// The incident radiance already divided by the probability density of the sample.
+    pub radiance: Color,
+}
+
+/// A light source which is not part of the scene geometry, so it can only be reached by sampling
+/// it from a shading point.
+pub trait LightSource: Send + Sync {
+    /// Sample the light arriving at point `p`. Return `None` if `p` receives no light.
+    fn sample(&self, p: Point3) -> Option<LightSample>;
+
+    /// Estimate the total emitted power, which is used to choose between light sources. Lights
+    /// infinitely far away count the power falling on a disk with the radius `scene_radius` of
+    /// the scene bounds.
+    fn power(&self, scene_radius: f32) -> f32;
+
+    /// The position of the light, which is `None` for lights infinitely far away.
+    fn position(&self) -> Option<Point3>;
+}
+
+/// The description of a light source in a scene file, tagged by `type`.
+#[derive(Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LightDesc {
+    Point(PointLight),
+    Spot(SpotLight),
+    Directional(DirectionalLight),
+}
+
+impl LightSource for LightDesc {
+    fn sample(&self, p: Point3) -> Option<LightSample> {
+        match self {
+            Self::Point(light) => light.sample(p),
+            Self::Spot(light) => light.sample(p),
+            Self::Directional(light) => light.sample(p),
+        }
+    }
+
+    fn power(&self, scene_radius: f32) -> f32 {
+        match self {
+            Self::Point(light) => light.power(scene_radius),
+            Self::Spot(light) => light.power(scene_radius),
+            Self::Directional(light) => light.power(scene_radius),
+        }
+    }
+
+    fn position(&self) -> Option<Point3> {
+        match self {
+            Self::Point(light) => light.position(),
+            Self::Spot(light) => light.position(),
+            Self::Directional(light) => light.position(),
+        }
+    }
+}
+
+/// The luminous efficacy in lm/W used to convert lumens and candelas into watts.
+pub const LUMINOUS_EFFICACY: f32 = 683.0;
+
+/// Get the fraction of light emitted at the cosine `cos` from the axis of a cone with half angle
+/// `angle`, which fades out smoothly over the `blend` fraction of the angle at the edge.
+pub fn cone_falloff(cos: f32, angle: f32, blend: f32) -> f32 {
+    let cos_outer = angle.cos();
+    let cos_inner = (angle * (1.0 - blend)).cos();
+    if cos >= cos_inner {
+        1.0
+    } else if cos <= cos_outer {
+        0.0
+    } else {
+        // Smoothstep between the outer and inner edge of the cone.
+        let t = (cos - cos_outer) / (cos_inner - cos_outer);
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+/// Integrate the cosine-weighted `profile` over the hemisphere around z-axis relative to an ideal
+/// diffuse emitter whose profile is one everywhere.
+pub fn hemisphere_fraction<F>(profile: F) -> f32
+where
+    F: Fn(Vec3) -> f32,
+{
+    const N_THETA: u32 = 64;
+    const N_PHI: u32 = 128;
+    let mut sum = 0.0;
+    for i in 0..N_THETA {
+        let theta = 0.5 * PI * (i as f32 + 0.5) / N_THETA as f32;
+        let (sin_theta, cos_theta) = theta.sin_cos();
+        for j in 0..N_PHI {
+            let phi = 2.0 * PI * (j as f32 + 0.5) / N_PHI as f32;
+            let local = Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta);
+            sum += profile(local) * cos_theta * sin_theta;
+        }
+    }
+    // The cosine-weighted hemisphere of a diffuse emitter integrates to pi.
+    sum * (0.5 * PI / N_THETA as f32) * (2.0 * PI / N_PHI as f32) / PI
+}
+
+/// Attenuate the intensity of a local light over `distance` with the falloff exponent, which is 2
+/// for the physical inverse-square law.
+fn attenuate(intensity: Color, distance: f32, falloff: f32) -> Color {
+    intensity / distance.powf(falloff)
+}
@@ -0,0 +1,57 @@
+use std::f32::consts::PI;
+
+use serde::Deserialize;
+
+use crate::{
+    color::{self, Color, luminance},
+    light::{LightSample, LightSource},
+    math::{Point3, Vec3},
+};
+
+/// A distant light such as the sun whose parallel rays arrive from the same direction everywhere.
+#[derive(Clone, Deserialize)]
+pub struct DirectionalLight {
+    /// The direction in which the light travels.
+    pub direction: Vec3,
+
+    /// The irradiance on a surface perpendicular to the light.
+    pub irradiance: Color,
+}
+
+impl Default for DirectionalLight {
+    /// Create a default white light shining straight down.
+    fn default() -> Self {
+        Self {
+            direction: Vec3::NEG_Y,
+            irradiance: color::WHITE,
+        }
+    }
+}
+
+impl DirectionalLight {
+    /// Create a directional light from the direction in which the light travels and irradiance.
+    pub const fn new(direction: Vec3, irradiance: Color) -> Self {
+        Self {
+            direction,
+            irradiance,
+        }
+    }
+}
+
+impl LightSource for DirectionalLight {
+    fn sample(&self, _p: Point3) -> Option<LightSample> {
+        Some(LightSample {
+            dir: -self.direction.normalize(),
+            distance: f32::INFINITY,
+            radiance: self.irradiance,
+        })
+    }
+
+    fn power(&self, scene_radius: f32) -> f32 {
+        PI * scene_radius * scene_radius * luminance(self.irradiance)
+    }
+
+    fn position(&self) -> Option<Point3> {
+        None
+    }
+}
@@ -0,0 +1,354 @@
+use std::f32::consts::PI;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::{
+    color::{self, Color, luminance},
+    light::{LUMINOUS_EFFICACY, LightSample, LightSource, attenuate, hemisphere_fraction},
+    math::{Point3, Vec3},
+    onb::ONB,
+};
+
+/// A photometric profile read from an IES LM-63 file with type C photometry, which gives the
+/// luminous intensity in every direction of a real fixture.
+///
+/// Directions are expressed in the local frame of the fixture whose z-axis is the aiming
+/// direction (vertical angle 0) and whose x-axis is horizontal angle 0.
+pub struct IesProfile {
+    /// The vertical angles in degrees measured from the aiming direction.
+    vertical: Vec<f32>,
+
+    /// The horizontal angles in degrees measured around the aiming direction.
+    horizontal: Vec<f32>,
+
+    /// The candela values for each horizontal angle and then each vertical angle.
+    candela: Vec<f32>,
+
+    /// The maximal candela value.
+    max_candela: f32,
+
+    /// The luminous flux in lumens integrated over the whole sphere.
+    flux: f32,
+}
+
+impl IesProfile {
+    /// Load the profile from an IES LM-63 file.
+    pub fn load<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Parse the profile from the content of an IES LM-63 file.
+    pub fn parse(content: &str) -> Result<Self> {
+        let invalid = |msg: &str| Error::new(ErrorKind::InvalidData, format!("IES: {msg}"));
+
+        // Skip the keywords before the tilt line, the numbers come after it.
+        let mut lines = content.lines();
+        let tilt = lines
+            .by_ref()
+            .find_map(|line| line.trim().strip_prefix("TILT="))
+            .ok_or_else(|| invalid("missing TILT line"))?
+            .trim()
+            .to_owned();
+        let rest: Vec<&str> = lines.collect();
+        let mut numbers = rest
+            .iter()
+            .flat_map(|line| line.split(|c: char| c.is_whitespace() || c == ','))
+            .filter(|token| !token.is_empty())
+            .map(|token| token.parse::<f32>().map_err(|_| invalid("invalid number")));
+        let mut next = || {
+            numbers
+                .next()
+                .unwrap_or_else(|| Err(invalid("unexpected end")))
+        };
+
+        if tilt == "INCLUDE" {
+            // Lamp-to-luminaire geometry, then the tilt angles and their multiplying factors.
+            next()?;
+            let pairs = next()? as usize;
+            for _ in 0..2 * pairs {
+                next()?;
+            }
+        }
+
+        let _num_lamps = next()?;
+        let _lumens_per_lamp = next()?;
+        let multiplier = next()?;
+        let num_vertical = next()? as usize;
+        let num_horizontal = next()? as usize;
+        let photometric_type = next()? as u32;
+        let _units = next()?;
+        let _width = next()?;
+        let _length = next()?;
+        let _height = next()?;
+        let ballast_factor = next()?;
+        let _ballast_lamp_factor = next()?;
+        let _input_watts = next()?;
+
+        if photometric_type != 1 {
+            return Err(invalid("only type C photometry is supported"));
+        }
+        if num_vertical == 0 || num_horizontal == 0 {
+            return Err(invalid("empty candela table"));
+        }
+
+        let vertical = (0..num_vertical)
+            .map(|_| next())
+            .collect::<Result<Vec<_>>>()?;
+        let horizontal = (0..num_horizontal)
+            .map(|_| next())
+            .collect::<Result<Vec<_>>>()?;
+        let candela = (0..num_vertical * num_horizontal)
+            .map(|_| next().map(|c| c * multiplier * ballast_factor))
+            .collect::<Result<Vec<_>>>()?;
+        let max_candela = candela.iter().copied().fold(0.0, f32::max);
+
+        let mut profile = Self {
+            vertical,
+            horizontal,
+            candela,
+            max_candela,
+            flux: 0.0,
+        };
+        profile.flux = sphere_integral(|local| profile.candela(local));
+        Ok(profile)
+    }
+
+    /// Get the luminous intensity in candela towards the unit direction `local` in the frame of
+    /// the fixture.
+    pub fn candela(&self, local: Vec3) -> f32 {
+        let v = local.z.clamp(-1.0, 1.0).acos().to_degrees();
+        let mut h = local.y.atan2(local.x).to_degrees().rem_euclid(360.0);
+
+        // Fold the horizontal angle according to the symmetry given by the last angle.
+        let last = *self.horizontal.last().unwrap();
+        if last <= 180.0 && h > 180.0 {
+            h = 360.0 - h;
+        }
+        if last <= 90.0 && h > 90.0 {
+            h = 180.0 - h;
+        }
+
+        let Some((vi, vt)) = Self::locate(&self.vertical, v) else {
+            return 0.0;
+        };
+        let Some((hi, ht)) = Self::locate(&self.horizontal, h) else {
+            // Use the nearest plane if the horizontal angles do not cover a full turn.
+            return self.column(self.horizontal.len() - 1, vi, vt);
+        };
+        let c0 = self.column(hi, vi, vt);
+        if ht <= 0.0 {
+            return c0;
+        }
+        let c1 = self.column(hi + 1, vi, vt);
+        c0 + (c1 - c0) * ht
+    }
+
+    /// Get the luminous intensity relative to the maximum of the profile towards the unit
+    /// direction `local` in the frame of the fixture.
+    pub fn relative(&self, local: Vec3) -> f32 {
+        if self.max_candela > 0.0 {
+            self.candela(local) / self.max_candela
+        } else {
+            0.0
+        }
+    }
+
+    /// Interpolate the candela values of the `h`-th horizontal angle between the `v`-th and the
+    /// next vertical angle.
+    fn column(&self, h: usize, v: usize, t: f32) -> f32 {
+        let n = self.vertical.len();
+        let c0 = self.candela[h * n + v];
+        if t <= 0.0 {
+            return c0;
+        }
+        let c1 = self.candela[h * n + v + 1];
+        c0 + (c1 - c0) * t
+    }
+
+    /// Find the segment of the sorted `angles` containing `angle`, and return its index and the
+    /// interpolation parameter inside it. Return `None` if `angle` is out of range.
+    fn locate(angles: &[f32], angle: f32) -> Option<(usize, f32)> {
+        if angles.len() == 1 {
+            return Some((0, 0.0));
+        }
+        if angle < angles[0] || angle > *angles.last().unwrap() {
+            return None;
+        }
+        let i = angles
+            .partition_point(|&a| a <= angle)
+            .saturating_sub(1)
+            .min(angles.len() - 2);
+        let width = angles[i + 1] - angles[i];
+        let t = if width > 0.0 {
+            ((angle - angles[i]) / width).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        Some((i, t))
+    }
+
+    /// Get the fraction of the flux of an ideal diffuse emitter which is emitted by a surface with
+    /// this profile relative to its maximum, integrated over the hemisphere around z-axis.
+    pub fn diffuse_fraction(&self) -> f32 {
+        hemisphere_fraction(|local| self.relative(local))
+    }
+
+    /// Get the luminous flux in lumens emitted by the fixture in all directions.
+    pub const fn flux(&self) -> f32 {
+        self.flux
+    }
+}
+
+/// Integrate `intensity` over the whole sphere of directions.
+fn sphere_integral<F>(intensity: F) -> f32
+where
+    F: Fn(Vec3) -> f32,
+{
+    const N_THETA: u32 = 128;
+    const N_PHI: u32 = 128;
+    let mut sum = 0.0;
+    for i in 0..N_THETA {
+        let theta = PI * (i as f32 + 0.5) / N_THETA as f32;
+        let (sin_theta, cos_theta) = theta.sin_cos();
+        for j in 0..N_PHI {
+            let phi = 2.0 * PI * (j as f32 + 0.5) / N_PHI as f32;
+            let local = Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta);
+            sum += intensity(local) * sin_theta;
+        }
+    }
+    sum * (PI / N_THETA as f32) * (2.0 * PI / N_PHI as f32)
+}
+
+/// A point light whose luminous intensity in every direction is given by an IES profile.
+#[derive(Clone)]
+pub struct IesLight {
+    /// The position of the light.
+    pub position: Point3,
+
+    /// The local frame whose z-axis is the aiming direction of the fixture.
+    frame: Arc<ONB>,
+
+    /// The photometric profile of the fixture.
+    pub profile: Arc<IesProfile>,
+
+    /// The tint and scale of the intensity given by the profile.
+    pub tint: Color,
+
+    /// The exponent of the distance falloff, 2 for the physical inverse-square law.
+    pub falloff: f32,
+}
+
+impl IesLight {
+    /// Create a light from position, aiming direction and the profile of the fixture.
+    pub fn new(position: Point3, direction: Vec3, profile: Arc<IesProfile>) -> Self {
+        Self {
+            position,
+            frame: Arc::new(ONB::new(direction)),
+            profile,
+            tint: color::WHITE,
+            falloff: 2.0,
+        }
+    }
+
+    /// Set the tint and scale of the intensity given by the profile.
+    pub const fn tint(mut self, tint: Color) -> Self {
+        self.tint = tint;
+        self
+    }
+
+    /// Set the exponent of the distance falloff.
+    pub const fn falloff(mut self, falloff: f32) -> Self {
+        self.falloff = falloff;
+        self
+    }
+}
+
+impl LightSource for IesLight {
+    fn sample(&self, p: Point3) -> Option<LightSample> {
+        let to_light = self.position - p;
+        let distance = to_light.length();
+        if distance <= 0.0 {
+            return None;
+        }
+        let dir = to_light / distance;
+        let candela = self.profile.candela(self.frame.local(-dir));
+        if candela <= 0.0 {
+            return None;
+        }
+        let intensity = self.tint * candela / LUMINOUS_EFFICACY;
+        Some(LightSample {
+            dir,
+            distance,
+            radiance: attenuate(intensity, distance, self.falloff),
+        })
+    }
+
+    fn power(&self, _scene_radius: f32) -> f32 {
+        self.profile.flux * luminance(self.tint) / LUMINOUS_EFFICACY
+    }
+
+    fn position(&self) -> Option<Point3> {
+        Some(self.position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The numbers of a rotationally symmetric profile after the tilt line.
+    const PHOTOMETRY: &str = "1 1000 2 3 1 1 2 0 0 0
+1 1 100
+0 45 90
+0
+100 80 20";
+
+    #[test]
+    fn parse_without_tilt() {
+        let content = format!("IESNA:LM-63-2002\n[TEST] minimal\nTILT=NONE\n{PHOTOMETRY}");
+        let profile = IesProfile::parse(&content).unwrap();
+        assert_eq!(profile.vertical, [0.0, 45.0, 90.0]);
+        assert_eq!(profile.horizontal, [0.0]);
+        assert_eq!(profile.candela, [200.0, 160.0, 40.0]);
+        assert_eq!(profile.max_candela, 200.0);
+
+        assert_eq!(profile.candela(Vec3::Z), 200.0);
+        assert_eq!(profile.candela(Vec3::X), 40.0);
+        assert_eq!(profile.candela(-Vec3::Z), 0.0);
+        let (sin, cos) = 22.5f32.to_radians().sin_cos();
+        let halfway = Vec3::new(sin, 0.0, cos);
+        assert!((profile.candela(halfway) - 180.0).abs() < 1e-2);
+        assert!((profile.relative(halfway) - 0.9).abs() < 1e-4);
+    }
+
+    #[test]
+    fn parse_with_tilt() {
+        let content = format!(
+            "IESNA:LM-63-2002\n[TEST] minimal\nTILT=INCLUDE\n1\n3\n0, 45, 90\n1, 0.9, 0.8\n{PHOTOMETRY}"
+        );
+        let profile = IesProfile::parse(&content).unwrap();
+        assert_eq!(profile.vertical, [0.0, 45.0, 90.0]);
+        assert_eq!(profile.candela, [200.0, 160.0, 40.0]);
+    }
+
+    #[test]
+    fn flux_of_isotropic_profile() {
+        let content = "TILT=NONE\n1 1000 1 1 1 1 2 0 0 0\n1 1 100\n0\n0\n100";
+        let profile = IesProfile::parse(content).unwrap();
+        let flux = 4.0 * PI * 100.0;
+        assert!((profile.flux() - flux).abs() < 1e-3 * flux);
+    }
+
+    #[test]
+    fn parse_rejects_invalid_files() {
+        assert!(IesProfile::parse(PHOTOMETRY).is_err());
+        assert!(IesProfile::parse("TILT=NONE\n1 1000 1 3 1").is_err());
+        let type_b = PHOTOMETRY.replacen("1 1 2 0", "1 2 2 0", 1);
+        assert!(IesProfile::parse(&format!("TILT=NONE\n{type_b}")).is_err());
+    }
+}
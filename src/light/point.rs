@@ -0,0 +1,78 @@
+use std::f32::consts::PI;
+
+use serde::Deserialize;
+
+use crate::{
+    color::{self, Color, luminance},
+    light::{LightSample, LightSource, attenuate},
+    math::Point3,
+};
+
+/// A light emitting equally in all directions from a single point.
+#[derive(Clone, Deserialize)]
+pub struct PointLight {
+    /// The position of the light.
+    pub position: Point3,
+
+    /// The radiant intensity of the light.
+    pub intensity: Color,
+
+    /// The exponent of the distance falloff, 2 for the physical inverse-square law.
+    #[serde(default = "default_falloff")]
+    pub falloff: f32,
+}
+
+const fn default_falloff() -> f32 {
+    2.0
+}
+
+impl Default for PointLight {
+    /// Create a default white point light at the origin.
+    fn default() -> Self {
+        Self {
+            position: Point3::ZERO,
+            intensity: color::WHITE,
+            falloff: default_falloff(),
+        }
+    }
+}
+
+impl PointLight {
+    /// Create a point light from position and radiant intensity.
+    pub fn new(position: Point3, intensity: Color) -> Self {
+        Self {
+            position,
+            intensity,
+            ..Default::default()
+        }
+    }
+
+    /// Set the exponent of the distance falloff.
+    pub const fn falloff(mut self, falloff: f32) -> Self {
+        self.falloff = falloff;
+        self
+    }
+}
+
+impl LightSource for PointLight {
+    fn sample(&self, p: Point3) -> Option<LightSample> {
+        let to_light = self.position - p;
+        let distance = to_light.length();
+        if distance <= 0.0 {
+            return None;
+        }
+        Some(LightSample {
+            dir: to_light / distance,
+            distance,
+            radiance: attenuate(self.intensity, distance, self.falloff),
+        })
+    }
+
+    fn power(&self, _scene_radius: f32) -> f32 {
+        4.0 * PI * luminance(self.intensity)
+    }
+
+    fn position(&self) -> Option<Point3> {
+        Some(self.position)
+    }
+}
@@ -0,0 +1,412 @@
+use std::sync::Arc;
+
+use crate::{
+    aabb::Aabb,
+    distribution::AliasTable,
+    environment::Environment,
+    light::{LightSample, LightSource},
+    math::{Point3, Vec3, random},
+    object::Object,
+    scene::Scene,
+    shape::{Bounded, bounding_sphere},
+};
+
+/// The strategy to choose one light source out of many at a shading point.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum LightStrategy {
+    /// Choose lights in proportion to their power through an alias table.
+    #[default]
+    Power,
+
+    /// Choose lights by their estimated contribution to the shading point through a light BVH.
+    Bvh,
+}
+
+/// A light source which is found by tracing rays towards it, so it is sampled by a direction.
+#[derive(Clone)]
+pub enum Emitter {
+    /// An object with an emissive material.
+    Object(Object),
+
+    /// The environment light of the scene.
+    Environment(Arc<dyn Environment>),
+}
+
+impl Emitter {
+    /// Sample a direction from point `p` towards the emitter.
+    pub fn sample(&self, p: Point3) -> Option<Vec3> {
+        match self {
+            Self::Object(object) => Some(object.sample_towards(p)),
+            Self::Environment(env) => env.sample(),
+        }
+    }
+
+    /// Return the probability density in solid angle of sampling direction `dir` from point `p`.
+    pub fn pdf(&self, p: Point3, dir: Vec3) -> f32 {
+        match self {
+            Self::Object(object) => object.pdf_towards(p, dir),
+            Self::Environment(env) => env.pdf(dir.normalize()),
+        }
+    }
+}
+
+/// Chooses one light out of many at a shading point by the `LightStrategy`. Lights infinitely
+/// far away are bounded by the scene they shine on, so they are chosen alongside finite lights.
+pub struct LightSelector {
+    /// The power distribution of the lights.
+    table: AliasTable,
+
+    /// The light BVH if the strategy is `LightStrategy::Bvh`.
+    bvh: Option<LightBvh>,
+}
+
+impl LightSelector {
+    /// Create a selector from the bounds and the power of each light.
+    pub fn new(lights: &[(Aabb, f32)], strategy: LightStrategy) -> Self {
+        let powers: Vec<f32> = lights.iter().map(|&(_, power)| power).collect();
+        let bvh =
+            (strategy == LightStrategy::Bvh && !lights.is_empty()).then(|| LightBvh::build(lights));
+        Self {
+            table: AliasTable::new(&powers),
+            bvh,
+        }
+    }
+
+    /// Choose a light for the shading point `p`. Return its index and probability.
+    pub fn select(&self, p: Point3) -> Option<(usize, f32)> {
+        if self.table.count() == 0 {
+            return None;
+        }
+        Some(match &self.bvh {
+            Some(bvh) => bvh.sample(p, random()),
+            None => self.table.sample(random()),
+        })
+    }
+
+    /// Return the probability of choosing the light in `index` for the shading point `p`.
+    pub fn pmf(&self, p: Point3, index: usize) -> f32 {
+        match &self.bvh {
+            Some(bvh) => bvh.pmf(p, index),
+            None => self.table.pmf(index),
+        }
+    }
+}
+
+/// A node of the light BVH.
+struct LightNode {
+    /// The bounding box of all lights below the node.
+    bounds: Aabb,
+
+    /// The total power of all lights below the node.
+    power: f32,
+
+    /// The indices of the two child nodes, which is `None` for a leaf.
+    children: Option<(usize, usize)>,
+
+    /// The index of the light in a leaf.
+    light: usize,
+
+    /// The center and radius of a sphere which encloses the bounding spheres of all lights below
+    /// the node.
+    sphere: (Point3, f32),
+}
+
+/// A bounding volume hierarchy over lights, which chooses a light by descending the tree with
+/// the probability of each child in proportion to its estimated contribution to the shading
+/// point.
+pub struct LightBvh {
+    /// The nodes of the tree where the first node is the root.
+    nodes: Vec<LightNode>,
+
+    /// The path from the root to each light where the bit of each depth is set for right child.
+    trails: Vec<u64>,
+}
+
+impl LightBvh {
+    /// Build the light BVH from the bounds and the power of each light.
+    pub fn build(lights: &[(Aabb, f32)]) -> Self {
+        let mut bvh = Self {
+            nodes: Vec::with_capacity(2 * lights.len()),
+            trails: vec![0; lights.len()],
+        };
+        let mut indices: Vec<usize> = (0..lights.len()).collect();
+        bvh.build_from_slice(lights, &mut indices, 0, 0);
+        bvh
+    }
+
+    /// Build the subtree of lights in `indices` whose path from the root is `trail` of `depth`.
+    /// Return the index of the subtree root.
+    fn build_from_slice(
+        &mut self,
+        lights: &[(Aabb, f32)],
+        indices: &mut [usize],
+        trail: u64,
+        depth: u32,
+    ) -> usize {
+        let node = self.nodes.len();
+        let mut bounds = lights[indices[0]].0;
+        let mut power = 0.0;
+        for &i in indices.iter() {
+            bounds = Aabb::surrounding_box(&bounds, &lights[i].0);
+            power += lights[i].1;
+        }
+        self.nodes.push(LightNode {
+            bounds,
+            power,
+            children: None,
+            light: indices[0],
+            sphere: bounding_sphere(&bounds),
+        });
+
+        // Stop at a single light, or at the depth limit of the trail by merging the rest.
+        if indices.len() == 1 || depth == u64::BITS - 1 {
+            self.trails[indices[0]] = trail;
+            return node;
+        }
+
+        // Split the lights in half along the longest axis of their centroids.
+        let centroid = |i: usize| bounding_sphere(&lights[i].0).0;
+        let mut centroid_bounds = Aabb::from_points(centroid(indices[0]), centroid(indices[0]));
+        for &i in indices.iter() {
+            centroid_bounds = Aabb::surrounding_box(
+                &centroid_bounds,
+                &Aabb::from_points(centroid(i), centroid(i)),
+            );
+        }
+        let axis = centroid_bounds.longest_axis();
+        indices.sort_by(|&a, &b| centroid(a)[axis].total_cmp(&centroid(b)[axis]));
+        let (left, right) = indices.split_at_mut(indices.len() / 2);
+
+        let left = self.build_from_slice(lights, left, trail, depth + 1);
+        let right = self.build_from_slice(lights, right, trail | (1 << depth), depth + 1);
+        self.nodes[node].children = Some((left, right));
+        self.nodes[node].sphere =
+            enclosing_sphere(self.nodes[left].sphere, self.nodes[right].sphere);
+        node
+    }
+
+    /// Estimate the contribution of the lights in the node to the shading point `p`.
+    fn importance(&self, node: usize, p: Point3) -> f32 {
+        let node = &self.nodes[node];
+        let (center, radius) = bounding_sphere(&node.bounds);
+        let distance_squared = (p - center).length_squared();
+        // Do not let the estimate blow up when the point is inside or close to the bounds.
+        node.power / distance_squared.max(radius * radius).max(1e-4)
+    }
+
+    /// Return the probability of descending into the left child of the node.
+    fn left_probability(&self, left: usize, right: usize, p: Point3) -> f32 {
+        let (wl, wr) = (self.importance(left, p), self.importance(right, p));
+        if wl + wr > 0.0 { wl / (wl + wr) } else { 0.5 }
+    }
+
+    /// Choose a light for the shading point `p` with an uniform random number `u`. Return its
+    /// index and probability.
+    pub fn sample(&self, p: Point3, mut u: f32) -> (usize, f32) {
+        let mut node = 0;
+        let mut prob = 1.0;
+        while let Some((left, right)) = self.nodes[node].children {
+            let pl = self.left_probability(left, right, p);
+            if u < pl {
+                u /= pl;
+                prob *= pl;
+                node = left;
+            } else {
+                u = ((u - pl) / (1.0 - pl)).min(1.0 - f32::EPSILON);
+                prob *= 1.0 - pl;
+                node = right;
+            }
+        }
+        (self.nodes[node].light, prob)
+    }
+
+    /// Return the probability of choosing the light in `index` for the shading point `p`.
+    pub fn pmf(&self, p: Point3, index: usize) -> f32 {
+        let trail = self.trails[index];
+        let mut node = 0;
+        let mut prob = 1.0;
+        let mut depth = 0;
+        while let Some((left, right)) = self.nodes[node].children {
+            let pl = self.left_probability(left, right, p);
+            if trail & (1 << depth) == 0 {
+                prob *= pl;
+                node = left;
+            } else {
+                prob *= 1.0 - pl;
+                node = right;
+            }
+            depth += 1;
+        }
+        if self.nodes[node].light == index {
+            prob
+        } else {
+            0.0
+        }
+    }
+
+    /// Call `f` with the index of each light whose bounding sphere is reached by the ray from
+    /// point `p` in direction `dir`. These are all the lights which could sample `dir`, since
+    /// shapes sample directions inside the cone of their bounding sphere.
+    pub fn visit_towards<F>(&self, p: Point3, dir: Vec3, mut f: F)
+    where
+        F: FnMut(usize),
+    {
+        let dir = dir.normalize();
+        let reaches = |(center, radius): (Point3, f32)| {
+            let to_center = center - p;
+            let along = to_center.dot(dir);
+            let distance_squared = to_center.length_squared();
+            distance_squared <= radius * radius
+                || (along > 0.0 && distance_squared - along * along <= radius * radius)
+        };
+
+        // The tree is at most 64 levels deep, so the stack never holds more than 64 nodes.
+        let mut stack = [0; u64::BITS as usize];
+        let mut len = 1;
+        while len > 0 {
+            len -= 1;
+            let node = &self.nodes[stack[len]];
+            if !reaches(node.sphere) {
+                continue;
+            }
+            match node.children {
+                Some((left, right)) => {
+                    stack[len] = left;
+                    stack[len + 1] = right;
+                    len += 2;
+                }
+                None => f(node.light),
+            }
+        }
+    }
+}
+
+/// Get the smallest sphere which encloses two spheres given by center and radius.
+fn enclosing_sphere((c1, r1): (Point3, f32), (c2, r2): (Point3, f32)) -> (Point3, f32) {
+    let distance = (c2 - c1).length();
+    if distance + r2 <= r1 {
+        return (c1, r1);
+    }
+    if distance + r1 <= r2 {
+        return (c2, r2);
+    }
+    let radius = 0.5 * (distance + r1 + r2);
+    (c1 + (c2 - c1) * ((radius - r1) / distance), radius)
+}
+
+/// Chooses and samples the light sources of a scene at shading points.
+pub struct LightSampler {
+    /// The emissive objects and the environment which are sampled by a direction.
+    emitters: Vec<Emitter>,
+
+    /// The number of emissive objects at the front of `emitters`.
+    objects: usize,
+
+    /// The light BVH of the emissive objects, which finds the ones that could sample a direction.
+    emitter_bvh: Option<LightBvh>,
+
+    /// The selector of `emitters`.
+    emitter_selector: LightSelector,
+
+    /// The light sources which are only reached by light sampling.
+    lights: Vec<Arc<dyn LightSource>>,
+
+    /// The selector of `lights`.
+    light_selector: LightSelector,
+}
+
+impl LightSampler {
+    /// Collect the light sources of the scene.
+    pub fn new(scene: &Scene) -> Self {
+        // Lights infinitely far away shine on the whole scene, so they are bounded by it.
+        let bounds = scene
+            .objects
+            .iter()
+            .map(Object::bbox)
+            .chain(
+                scene
+                    .lights
+                    .iter()
+                    .filter_map(|light| light.position())
+                    .map(|p| Aabb::from_points(p, p)),
+            )
+            .reduce(|a, b| Aabb::surrounding_box(&a, &b))
+            .unwrap_or_default();
+        let (_, radius) = bounding_sphere(&bounds);
+
+        let mut emitters = Vec::new();
+        let mut emitter_items = Vec::new();
+        for obj in &scene.objects {
+            let power = obj.material.power(obj.area());
+            if power > 0.0 {
+                emitters.push(Emitter::Object(obj.clone()));
+                emitter_items.push((obj.bbox(), power));
+            }
+        }
+        let objects = emitter_items.len();
+        let emitter_bvh = (objects > 0).then(|| LightBvh::build(&emitter_items));
+        if let Some(env) = &scene.environment {
+            emitters.push(Emitter::Environment(env.clone()));
+            emitter_items.push((bounds, env.power(radius)));
+        }
+
+        let light_items: Vec<_> = scene
+            .lights
+            .iter()
+            .map(|light| {
+                let bounds = light.position().map_or(bounds, |p| Aabb::from_points(p, p));
+                (bounds, light.power(radius))
+            })
+            .collect();
+
+        Self {
+            emitters,
+            objects,
+            emitter_bvh,
+            emitter_selector: LightSelector::new(&emitter_items, scene.light_strategy),
+            lights: scene.lights.clone(),
+            light_selector: LightSelector::new(&light_items, scene.light_strategy),
+        }
+    }
+
+    /// Determine whether the scene has emitters which could be sampled by a direction.
+    pub fn has_emitters(&self) -> bool {
+        !self.emitters.is_empty()
+    }
+
+    /// Choose an emitter for point `p` and sample a direction towards it.
+    pub fn sample_emitter(&self, p: Point3) -> Option<Vec3> {
+        let (index, _) = self.emitter_selector.select(p)?;
+        self.emitters[index].sample(p)
+    }
+
+    /// Return the probability density in solid angle of sampling direction `dir` from point `p`
+    /// by `sample_emitter`.
+    pub fn emitter_pdf(&self, p: Point3, dir: Vec3) -> f32 {
+        let pdf = |i: usize| {
+            let pdf = self.emitters[i].pdf(p, dir);
+            if pdf > 0.0 {
+                pdf * self.emitter_selector.pmf(p, i)
+            } else {
+                0.0
+            }
+        };
+
+        // Only look up the emissive objects whose bounds are in the direction, besides the
+        // environment which could sample any direction.
+        let mut total: f32 = (self.objects..self.emitters.len()).map(pdf).sum();
+        if let Some(bvh) = &self.emitter_bvh {
+            bvh.visit_towards(p, dir, |i| total += pdf(i));
+        }
+        total
+    }
+
+    /// Choose a light source for point `p` and sample the light arriving from it, whose radiance
+    /// is already divided by the probability of the choice.
+    pub fn sample_light(&self, p: Point3) -> Option<LightSample> {
+        let (index, prob) = self.light_selector.select(p)?;
+        let mut sample = self.lights[index].sample(p)?;
+        sample.radiance /= prob;
+        Some(sample)
+    }
+}
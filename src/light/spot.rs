@@ -0,0 +1,106 @@
+use std::f32::consts::PI;
+
+use serde::Deserialize;
+
+use crate::{
+    color::{self, Color, luminance},
+    light::{LightSample, LightSource, attenuate, cone_falloff},
+    math::{Point3, Vec3},
+};
+
+/// A point light which only emits inside a cone and fades out towards the edge of the cone.
+#[derive(Clone, Deserialize)]
+pub struct SpotLight {
+    /// The position of the light.
+    pub position: Point3,
+
+    /// The direction of the cone axis.
+    pub direction: Vec3,
+
+    /// The radiant intensity of the light along the cone axis.
+    pub intensity: Color,
+
+    /// The half angle of the cone in radians.
+    pub angle: f32,
+
+    /// The fraction of the cone angle in [0, 1] over which the light fades out at the edge.
+    #[serde(default)]
+    pub blend: f32,
+
+    /// The exponent of the distance falloff, 2 for the physical inverse-square law.
+    #[serde(default = "default_falloff")]
+    pub falloff: f32,
+}
+
+const fn default_falloff() -> f32 {
+    2.0
+}
+
+impl SpotLight {
+    /// Create a spot light from position, cone direction, radiant intensity and the half angle of
+    /// the cone in radians.
+    pub fn new(position: Point3, direction: Vec3, intensity: Color, angle: f32) -> Self {
+        Self {
+            position,
+            direction,
+            intensity,
+            angle,
+            blend: 0.0,
+            falloff: default_falloff(),
+        }
+    }
+
+    /// Set the fraction of the cone angle over which the light fades out at the edge.
+    pub const fn blend(mut self, blend: f32) -> Self {
+        self.blend = blend.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Set the exponent of the distance falloff.
+    pub const fn falloff(mut self, falloff: f32) -> Self {
+        self.falloff = falloff;
+        self
+    }
+
+    /// Get the fraction of the intensity emitted towards the unit direction `dir`.
+    fn cone_factor(&self, dir: Vec3) -> f32 {
+        cone_falloff(dir.dot(self.direction.normalize()), self.angle, self.blend)
+    }
+}
+
+impl LightSource for SpotLight {
+    fn sample(&self, p: Point3) -> Option<LightSample> {
+        let to_light = self.position - p;
+        let distance = to_light.length();
+        if distance <= 0.0 {
+            return None;
+        }
+        let dir = to_light / distance;
+        let factor = self.cone_factor(-dir);
+        if factor <= 0.0 {
+            return None;
+        }
+        Some(LightSample {
+            dir,
+            distance,
+            radiance: factor * attenuate(self.intensity, distance, self.falloff),
+        })
+    }
+
+    fn power(&self, _scene_radius: f32) -> f32 {
+        // Approximate the cone by its half-blended angle.
+        let cos = (self.angle * (1.0 - 0.5 * self.blend)).cos();
+        2.0 * PI * (1.0 - cos) * luminance(self.intensity)
+    }
+
+    fn position(&self) -> Option<Point3> {
+        Some(self.position)
+    }
+}
+
+impl Default for SpotLight {
+    /// Create a default white spot light at the origin pointing down with a 30 degree cone.
+    fn default() -> Self {
+        Self::new(Point3::ZERO, Vec3::NEG_Y, color::WHITE, 30f32.to_radians())
+    }
+}
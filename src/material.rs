@@ -2,28 +2,34 @@ use std::f32;
 
 use crate::{
     color::{self, Color},
-    math::{Point3, Ray},
+    math::Ray,
     shape::HitRecord,
 };
 
 pub mod dieletrics;
 pub mod isotropic;
 pub mod lambertian;
+pub mod layered;
 pub mod light;
 pub mod metal;
+pub mod microfacet;
+pub mod mix;
+pub mod normal_map;
+pub mod oren_nayar;
+pub mod principled;
+pub mod sheen;
+pub mod subsurface;
+pub mod thin_film;
 
 pub trait Material: Send + Sync {
     /// Get the attenuation color and scattered ray to be able to compute the scattered color.
-    fn scatter(
-        &self,
-        _r_in: &Ray,
-        _rec: &HitRecord,
-    ) -> Option<(Color, Ray)> {
+    fn scatter(&self, _r_in: &Ray, _rec: &HitRecord) -> Option<(Color, Ray)> {
         None
     }
 
-    /// Get the emitted color of the material at the given uv coordinate and position. No emit by default (return `color::Black`).
-    fn emit(&self, _u: f32, _v: f32, _p: Point3) -> Color {
+    /// Get the emitted color of the material towards the incident ray at the intersection. No emit
+    /// by default (return `color::Black`).
+    fn emit(&self, _r_in: &Ray, _rec: &HitRecord) -> Color {
         color::BLACK
     }
 
@@ -38,4 +44,40 @@ pub trait Material: Send + Sync {
     fn scatter_pdf(&self, _r_in: &Ray, _r_out: &Ray, _rec: &HitRecord) -> f32 {
         1.0 / (2.0 * f32::consts::PI)
     }
+
+    /// Evaluate the scattering function multiplied by the cosine term from `r_in` to an arbitrary
+    /// outgoing ray `r_out`, which is used when `r_out` is chosen by light sampling.
+    fn eval(&self, _r_in: &Ray, _r_out: &Ray, _rec: &HitRecord) -> Color {
+        color::BLACK
+    }
+
+    /// Estimate the total power emitted by a surface of `area` with the material, which is used to
+    /// choose between light sources. Zero for non-emissive materials.
+    fn power(&self, _area: f32) -> f32 {
+        0.0
+    }
+
+    /// Set the surface area of the shape the material is attached to, for materials which convert
+    /// their power into radiance. Do nothing by default.
+    fn set_area(&mut self, _area: f32) {}
+
+    /// Whether the scattered ray is chosen deterministically (e.g. mirror reflection), so that
+    /// the attenuation from `scatter` is used directly and light sampling is skipped.
+    fn is_specular(&self) -> bool {
+        false
+    }
+
+    /// Get the attenuation color and scattered ray like `scatter`, and whether this sample is
+    /// specular, for materials which are specular for some samples only. Default to `scatter` and
+    /// `is_specular`.
+    fn sample_scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Color, Ray, bool)> {
+        let (attenuation, scatter) = self.scatter(r_in, rec)?;
+        Some((attenuation, scatter, self.is_specular()))
+    }
+
+    /// Whether the scattering depends on the wavelength of the incident ray, so that the path
+    /// has to be collapsed onto its hero wavelength.
+    fn is_dispersive(&self) -> bool {
+        false
+    }
 }
@@ -2,13 +2,61 @@ use std::sync::Arc;
 
 use crate::{
     color::{self, Color},
-    material::Material,
+    material::{
+        Material,
+        microfacet::{Ggx, fresnel_dielectric, local_directions, shading_frame},
+        thin_film::ThinFilm,
+    },
     math::random,
     math::{Ray, Vec3},
     shape::HitRecord,
+    spectrum::WAVELENGTH_DEFAULT,
     texture::{Texture, solid_color::SolidColor},
 };
 
+/// A model of the index of refraction varying with wavelength, which splits white light into
+/// its colors.
+#[derive(Clone, Copy, Debug)]
+pub enum Dispersion {
+    /// Cauchy's equation `n = a + b / λ²` with wavelength `λ` in micrometers.
+    Cauchy { a: f32, b: f32 },
+
+    /// Sellmeier's equation `n² = 1 + Σ bᵢλ² / (λ² - cᵢ)` with wavelength `λ` in micrometers.
+    Sellmeier { b: [f32; 3], c: [f32; 3] },
+}
+
+impl Dispersion {
+    /// The borosilicate crown glass BK7.
+    pub const BK7: Self = Self::Sellmeier {
+        b: [1.039_612, 0.231_792_34, 1.010_469_4],
+        c: [0.006_000_699, 0.020_017_914, 103.560_65],
+    };
+
+    /// The dense flint glass SF11.
+    pub const SF11: Self = Self::Sellmeier {
+        b: [1.737_596_9, 0.313_747_35, 1.898_781],
+        c: [0.013_188_707, 0.062_306_814, 155.236_3],
+    };
+
+    /// Diamond.
+    pub const DIAMOND: Self = Self::Sellmeier {
+        b: [0.3306, 4.3356, 0.0],
+        c: [0.030_625, 0.011_236, 0.0],
+    };
+
+    /// Get the index of refraction at `wavelength` in nanometers.
+    pub fn index(&self, wavelength: f32) -> f32 {
+        let l2 = (wavelength / 1000.0).powi(2);
+        match *self {
+            Self::Cauchy { a, b } => a + b / l2,
+            Self::Sellmeier { b, c } => {
+                let n2 = 1.0 + (0..3).map(|i| b[i] * l2 / (l2 - c[i])).sum::<f32>();
+                n2.max(1.0).sqrt()
+            }
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Dielectric {
     /// The index of refraction of the dielectric material.
@@ -16,6 +64,19 @@ pub struct Dielectric {
 
     /// The texture of the material.
     pub tex: Arc<dyn Texture>,
+
+    /// The distribution of microfacet normals, which is smooth by default.
+    pub distribution: Ggx,
+
+    /// The absorption coefficient per unit length inside the material, applied by the
+    /// Beer-Lambert law over the distance traveled between entering and leaving.
+    pub absorption: Color,
+
+    /// The variation of the index of refraction with wavelength, which replaces `index` if set.
+    pub dispersion: Option<Dispersion>,
+
+    /// The optional thin film coating the surface.
+    pub thin_film: Option<ThinFilm>,
 }
 
 impl Default for Dielectric {
@@ -23,6 +84,10 @@ impl Default for Dielectric {
         Self {
             index: 1.5,
             tex: Arc::new(SolidColor::new(color::WHITE)),
+            distribution: Ggx::new(0.0, 0.0),
+            absorption: Color::ZERO,
+            dispersion: None,
+            thin_film: None,
         }
     }
 }
@@ -33,6 +98,10 @@ impl Dielectric {
         Self {
             index: index_of_refraction,
             tex: Arc::new(SolidColor::new(color)),
+            distribution: Ggx::new(0.0, 0.0),
+            absorption: Color::ZERO,
+            dispersion: None,
+            thin_film: None,
         }
     }
 
@@ -44,34 +113,182 @@ impl Dielectric {
         Self {
             index: index_of_refraction,
             tex: Arc::new(tex),
+            distribution: Ggx::new(0.0, 0.0),
+            absorption: Color::ZERO,
+            dispersion: None,
+            thin_film: None,
+        }
+    }
+
+    /// Set the roughness of the surface to get frosted glass.
+    pub fn roughness(mut self, roughness: f32) -> Self {
+        self.distribution = Ggx::new(roughness, roughness);
+        self
+    }
+
+    /// Set the absorption coefficient per unit length inside the material.
+    pub const fn absorption(mut self, coefficient: Color) -> Self {
+        self.absorption = coefficient;
+        self
+    }
+
+    /// Set the absorption inside the material from the color transmitted after traveling
+    /// `distance` through it.
+    pub fn transmittance(mut self, color: Color, distance: f32) -> Self {
+        let color = color.clamp(Color::splat(1e-6), Color::ONE);
+        self.absorption = -color.ln() / distance;
+        self
+    }
+
+    /// Set the variation of the index of refraction with wavelength. The index at the default
+    /// wavelength becomes the nominal `index`.
+    pub fn dispersion(mut self, dispersion: Dispersion) -> Self {
+        self.index = dispersion.index(WAVELENGTH_DEFAULT);
+        self.dispersion = Some(dispersion);
+        self
+    }
+
+    /// Coat the surface with a thin film producing iridescent interference colors, like soap
+    /// bubbles or coated lenses.
+    pub fn thin_film(mut self, film: ThinFilm) -> Self {
+        self.thin_film = Some(film);
+        self
+    }
+
+    /// Get the fraction of light left after the incident ray traveled inside the material, which
+    /// is the case when it hits the back face.
+    fn interior_transmittance(&self, r_in: &Ray, rec: &HitRecord) -> Color {
+        if rec.front_face || self.absorption == Color::ZERO {
+            return color::WHITE;
+        }
+        let distance = (rec.p - r_in.ori).length();
+        (-self.absorption * distance).exp()
+    }
+
+    /// Get the index of refraction of the far side over the near side of the surface at the
+    /// wavelength of the incident ray.
+    fn relative_index(&self, r_in: &Ray, rec: &HitRecord) -> f32 {
+        let index = self
+            .dispersion
+            .map_or(self.index, |dispersion| dispersion.index(r_in.wavelength));
+        if rec.front_face { index } else { 1.0 / index }
+    }
+
+    /// Get the Fresnel reflectance at the incident angle with cosine `cos`, which varies with
+    /// color only if the surface is coated by a thin film.
+    fn fresnel(&self, cos: f32, eta: f32, rec: &HitRecord) -> Color {
+        let Some(film) = &self.thin_film else {
+            return Color::splat(fresnel_dielectric(cos, eta));
+        };
+
+        // The film cannot transmit what the surface reflects totally, and its clamped reflectance
+        // might be less than one.
+        if (1.0 - cos * cos) / (eta * eta) >= 1.0 {
+            return color::WHITE;
         }
+        let (outer, inner) = if rec.front_face {
+            (1.0, eta)
+        } else {
+            (1.0 / eta, 1.0)
+        };
+        film.reflectance(cos, outer, Color::splat(inner), Color::ZERO, rec)
     }
 }
 
+/// Get the probability of choosing reflection over refraction from the Fresnel reflectance.
+fn reflect_probability(fresnel: Color) -> f32 {
+    fresnel.element_sum() / 3.0
+}
+
 impl Material for Dielectric {
     fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Color, Ray)> {
-        let eta = if rec.front_face {
-            1.0 / self.index
-        } else {
-            self.index
-        };
+        let eta = self.relative_index(r_in, rec);
         let unit_direction = r_in.dir.normalize();
+        let attenuation = self.tex.sample_hit(rec) * self.interior_transmittance(r_in, rec);
 
-        // Ensure the incident ray has refract ray
-        let cos_theta = -unit_direction.dot(rec.normal).min(1.0);
-        let sin_theta = (cos_theta.mul_add(-cos_theta, 1.0)).sqrt();
-        let refractable = eta * sin_theta < 1.0;
-
-        // The larger the incident angle, and the more reflection component the human eye sees
-        let direction: Vec3 =
-            if refractable && Self::reflectance(self, cos_theta, self.index) <= random() {
-                unit_direction.refract(rec.normal, eta)
+        if self.distribution.is_smooth() {
+            // The larger the incident angle, and the more reflection component the human eye sees
+            let cos_theta = -unit_direction.dot(rec.shading_normal).min(1.0);
+            let fresnel = self.fresnel(cos_theta, eta, rec);
+            let probability = reflect_probability(fresnel);
+            let (direction, weight): (Vec3, Color) = if probability <= random() {
+                (
+                    unit_direction.refract(rec.shading_normal, 1.0 / eta),
+                    (Color::ONE - fresnel) / (1.0 - probability),
+                )
             } else {
-                unit_direction.reflect(rec.normal)
+                (
+                    unit_direction.reflect(rec.shading_normal),
+                    fresnel / probability,
+                )
             };
+            let scatter = Ray::new(rec.p, direction, r_in.t);
+            return Some((attenuation * weight, scatter));
+        }
+
+        // Reflect or refract through a microfacet normal visible from the incident direction, by
+        // the Fresnel reflectance of that microfacet.
+        let frame = shading_frame(rec);
+        let wo = frame.local(-unit_direction);
+        if wo.z <= 0.0 {
+            return None;
+        }
+        let wm = self.distribution.sample_wm(wo);
+        let fresnel = self.fresnel(wo.dot(wm), eta, rec);
+        let probability = reflect_probability(fresnel);
+        let reflected = probability > random();
+        let wi = if reflected {
+            (-wo).reflect(wm)
+        } else {
+            (-wo).refract(wm, 1.0 / eta)
+        };
+        let scatter = Ray::new(rec.p, frame.transform(wi), r_in.t);
 
-        let attenuation = self.tex.sample(rec.u, rec.v, rec.p);
-        let scatter = Ray::new(rec.p, direction, rec.t);
+        // The weight of the VNDF sample is G2 / G1 for both reflection and refraction, times the
+        // ratio of the colored Fresnel term to the probability of the choice.
+        let (valid, weight) = if reflected {
+            (wi.z > 0.0, fresnel / probability)
+        } else {
+            (wi.z < 0.0, (Color::ONE - fresnel) / (1.0 - probability))
+        };
+        let attenuation = if valid {
+            attenuation * weight * self.distribution.g(wo, wi) / self.distribution.g1(wo)
+        } else {
+            color::BLACK
+        };
         Some((attenuation, scatter))
     }
+
+    fn scatter_pdf(&self, r_in: &Ray, r_out: &Ray, rec: &HitRecord) -> f32 {
+        let (_, wo, wi) = local_directions(r_in, r_out, rec);
+        let eta = self.relative_index(r_in, rec);
+        if let Some((wm, _, pdf)) = self.distribution.eval_reflection(wo, wi) {
+            pdf * reflect_probability(self.fresnel(wo.dot(wm), eta, rec))
+        } else if let Some((wm, _, pdf)) = self.distribution.eval_refraction(wo, wi, eta) {
+            pdf * (1.0 - reflect_probability(self.fresnel(wo.dot(wm), eta, rec)))
+        } else {
+            0.0
+        }
+    }
+
+    fn eval(&self, r_in: &Ray, r_out: &Ray, rec: &HitRecord) -> Color {
+        let (_, wo, wi) = local_directions(r_in, r_out, rec);
+        let eta = self.relative_index(r_in, rec);
+        let tint = self.tex.sample_hit(rec) * self.interior_transmittance(r_in, rec);
+        if let Some((wm, value, _)) = self.distribution.eval_reflection(wo, wi) {
+            tint * value * self.fresnel(wo.dot(wm), eta, rec)
+        } else if let Some((wm, value, _)) = self.distribution.eval_refraction(wo, wi, eta) {
+            tint * value * (Color::ONE - self.fresnel(wo.dot(wm), eta, rec))
+        } else {
+            color::BLACK
+        }
+    }
+
+    fn is_specular(&self) -> bool {
+        self.distribution.is_smooth()
+    }
+
+    fn is_dispersive(&self) -> bool {
+        self.dispersion.is_some()
+    }
 }
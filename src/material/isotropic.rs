@@ -41,13 +41,17 @@ impl Isotropic {
 }
 
 impl Material for Isotropic {
-    fn scatter(&self, r_in: &Ray, rec: &crate::shape::HitRecord) -> Option<(Color, Ray)> {
-        let attenuation = self.tex.sample(rec.u, rec.v, rec.p);
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Color, Ray)> {
+        let attenuation = self.tex.sample_hit(rec);
         let scatter = Ray::new(rec.p, random_unit_vector(), r_in.t);
         Some((attenuation, scatter))
     }
-    
+
     fn scatter_pdf(&self, _r_in: &Ray, _r_out: &Ray, _rec: &HitRecord) -> f32 {
         1.0 / (4.0 * f32::consts::PI)
     }
+
+    fn eval(&self, r_in: &Ray, r_out: &Ray, rec: &HitRecord) -> Color {
+        self.tex.sample_hit(rec) * self.scatter_pdf(r_in, r_out, rec)
+    }
 }
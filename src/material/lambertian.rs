@@ -44,20 +44,24 @@ impl Lambertian {
 
 impl Material for Lambertian {
     fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Color, Ray)> {
-        let uvw = ONB::new(rec.normal);
+        let uvw = ONB::new(rec.shading_normal);
         let mut scatter_direction = uvw.transform(random_cosine_weight_on_hemisphere());
 
         if scatter_direction.near_zero() {
-            scatter_direction = rec.normal;
+            scatter_direction = rec.shading_normal;
         }
 
-        let attenuation = self.tex.sample(rec.u, rec.v, rec.normal);
+        let attenuation = self.tex.sample_hit(rec);
         let scatter = Ray::new(rec.p, scatter_direction, r_in.t);
         Some((attenuation, scatter))
     }
 
     fn scatter_pdf(&self, _r_in: &Ray, r_out: &Ray, rec: &HitRecord) -> f32 {
-        let cos = rec.normal.dot(r_out.dir.normalize());
-        cos / f32::consts::PI
+        let cos = rec.shading_normal.dot(r_out.dir.normalize());
+        cos.max(0.0) / f32::consts::PI
+    }
+
+    fn eval(&self, r_in: &Ray, r_out: &Ray, rec: &HitRecord) -> Color {
+        self.tex.sample_hit(rec) * self.scatter_pdf(r_in, r_out, rec)
     }
 }
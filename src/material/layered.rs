@@ -0,0 +1,300 @@
+use std::sync::Arc;
+
+use crate::{
+    color::{self, Color},
+    material::{
+        Material,
+        microfacet::{Ggx, fresnel_dielectric, local_directions, shading_frame},
+    },
+    math::{Ray, random},
+    shape::HitRecord,
+};
+
+/// The roughness below which the coat is clamped to keep its reflection evaluable.
+const MIN_ROUGHNESS: f32 = 0.02;
+
+/// The minimal probability of sampling the coat, so that its highlights are found even at normal
+/// incidence where it reflects little.
+const MIN_COAT_PROBABILITY: f32 = 0.25;
+
+/// The number of samples used to integrate the average Fresnel reflectance of the coat.
+const FRESNEL_AVERAGE_SAMPLES: usize = 32;
+
+/// A dielectric coat over an arbitrary base material, like varnished wood or car paint.
+///
+/// Light reflects off the rough coat by its Fresnel reflectance, and the rest is transmitted to
+/// the base, absorbed along the way through the coat. The base is scaled by the transmittance
+/// in and out of the coat and normalized by the average transmittance, which accounts for light
+/// reflected back inside, so that a clear coat over a white diffuse base conserves energy
+/// exactly. The coat covers the front face only. Samples of a smooth coat or a specular base are
+/// followed alone, while the other layers are lit by light sampling.
+#[derive(Clone)]
+pub struct Layered {
+    /// The material below the coat.
+    pub base: Arc<dyn Material>,
+
+    /// The index of refraction of the coat.
+    pub ior: f32,
+
+    /// The distribution of microfacet normals of the coat.
+    pub distribution: Ggx,
+
+    /// The thickness of the coat.
+    pub thickness: f32,
+
+    /// The absorption coefficient per unit length inside the coat.
+    pub absorption: Color,
+
+    /// The Fresnel reflectance of the coat averaged over the cosine-weighted hemisphere.
+    average_fresnel: f32,
+}
+
+impl Layered {
+    /// Create a clear and smooth coat with index of refraction 1.5 over the base material.
+    pub fn new<M>(base: M) -> Self
+    where
+        M: Material + 'static,
+    {
+        Self {
+            base: Arc::new(base),
+            ior: 1.5,
+            distribution: Ggx::new(MIN_ROUGHNESS, MIN_ROUGHNESS),
+            thickness: 1.0,
+            absorption: Color::ZERO,
+            average_fresnel: average_fresnel(1.5),
+        }
+    }
+
+    /// Set the index of refraction of the coat.
+    pub fn ior(mut self, ior: f32) -> Self {
+        self.ior = ior;
+        self.average_fresnel = average_fresnel(ior);
+        self
+    }
+
+    /// Set the roughness of the coat.
+    pub fn roughness(mut self, roughness: f32) -> Self {
+        let roughness = roughness.max(MIN_ROUGHNESS);
+        self.distribution = Ggx::new(roughness, roughness);
+        self
+    }
+
+    /// Set the thickness of the coat, which scales its absorption.
+    pub const fn thickness(mut self, thickness: f32) -> Self {
+        self.thickness = thickness;
+        self
+    }
+
+    /// Set the absorption coefficient per unit length inside the coat.
+    pub const fn absorption(mut self, coefficient: Color) -> Self {
+        self.absorption = coefficient;
+        self
+    }
+
+    /// Set the absorption inside the coat from the color transmitted straight through a coat of
+    /// unit thickness.
+    pub fn tint(mut self, color: Color) -> Self {
+        let color = color.clamp(Color::splat(1e-6), Color::ONE);
+        self.absorption = -color.ln();
+        self
+    }
+
+    /// Get the fraction of light left after crossing the coat at the angle with cosine `cos`
+    /// outside the coat.
+    fn transmittance(&self, cos: f32) -> Color {
+        if self.absorption == Color::ZERO {
+            return color::WHITE;
+        }
+        let sin2 = (1.0 - cos * cos) / (self.ior * self.ior);
+        let cos_inside = (1.0 - sin2).max(1e-4).sqrt();
+        (-self.absorption * self.thickness / cos_inside).exp()
+    }
+
+    /// Get the weight of the base from the local z-components of the directions towards the
+    /// viewer `wo` and the light `wi`. Light transmitted through the base only crosses the coat
+    /// once.
+    fn base_weight(&self, wo: f32, wi: f32) -> Color {
+        let weight = (1.0 - fresnel_dielectric(wo, self.ior)) * self.transmittance(wo);
+        if wi <= 0.0 {
+            return weight;
+        }
+        let weight = weight * (1.0 - fresnel_dielectric(wi, self.ior)) * self.transmittance(wi);
+        if self.base.is_specular() {
+            weight
+        } else {
+            weight / (1.0 - self.average_fresnel)
+        }
+    }
+
+    /// Get the probability of sampling the coat rather than the base.
+    fn coat_probability(&self, wo: f32) -> f32 {
+        fresnel_dielectric(wo, self.ior).max(MIN_COAT_PROBABILITY)
+    }
+
+    /// Get the probabilities of sampling the non-specular coat and base, which are zero for the
+    /// layers which are specular.
+    fn non_specular_probabilities(&self, wo: f32) -> (f32, f32) {
+        let probability = self.coat_probability(wo);
+        let coat = if self.distribution.is_smooth() {
+            0.0
+        } else {
+            probability
+        };
+        let base = if self.base.is_specular() {
+            0.0
+        } else {
+            1.0 - probability
+        };
+        (coat, base)
+    }
+}
+
+/// Get the Fresnel reflectance of a coat with index of refraction `ior` averaged over the
+/// cosine-weighted hemisphere.
+fn average_fresnel(ior: f32) -> f32 {
+    let n = FRESNEL_AVERAGE_SAMPLES as f32;
+    (0..FRESNEL_AVERAGE_SAMPLES)
+        .map(|i| {
+            let cos = (i as f32 + 0.5) / n;
+            2.0 * fresnel_dielectric(cos, ior) * cos / n
+        })
+        .sum()
+}
+
+impl Material for Layered {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Color, Ray)> {
+        let (attenuation, scatter, _) = self.sample_scatter(r_in, rec)?;
+        Some((attenuation, scatter))
+    }
+
+    fn sample_scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Color, Ray, bool)> {
+        if !rec.front_face {
+            return self.base.sample_scatter(r_in, rec);
+        }
+        let frame = shading_frame(rec);
+        let wo = frame.local(-r_in.dir.normalize());
+        if wo.z <= 0.0 {
+            return None;
+        }
+        let probability = self.coat_probability(wo.z);
+
+        // Follow a sample of a specular layer alone, while samples of the non-specular layers are
+        // weighted by their blended scattering function and density.
+        let scatter = if random() < probability {
+            let wi = self.distribution.sample_reflection(wo);
+            let scatter = Ray::new(rec.p, frame.transform(wi), r_in.t);
+            if self.distribution.is_smooth() {
+                let attenuation = if wi.z > 0.0 {
+                    let wm = (wo + wi).normalize();
+                    fresnel_dielectric(wo.dot(wm), self.ior) * self.distribution.g(wo, wi)
+                        / self.distribution.g1(wo)
+                        / probability
+                } else {
+                    0.0
+                };
+                return Some((Color::splat(attenuation), scatter, true));
+            }
+            scatter
+        } else {
+            let (attenuation, scatter, specular) = self.base.sample_scatter(r_in, rec)?;
+            if specular {
+                let wi = frame.local(scatter.dir.normalize());
+                let weight = self.base_weight(wo.z, wi.z) / (1.0 - probability);
+                return Some((attenuation * weight, scatter, true));
+            }
+            scatter
+        };
+
+        let pdf = self.scatter_pdf(r_in, &scatter, rec);
+        let attenuation = if pdf > 0.0 {
+            self.eval(r_in, &scatter, rec) / pdf
+        } else {
+            color::BLACK
+        };
+        Some((attenuation, scatter, false))
+    }
+
+    fn scatter_pdf(&self, r_in: &Ray, r_out: &Ray, rec: &HitRecord) -> f32 {
+        if !rec.front_face {
+            return self.base.scatter_pdf(r_in, r_out, rec);
+        }
+        let (_, wo, wi) = local_directions(r_in, r_out, rec);
+        let (coat_probability, base_probability) = self.non_specular_probabilities(wo.z);
+        let total = coat_probability + base_probability;
+        if total <= 0.0 {
+            return 0.0;
+        }
+        let coat = if coat_probability > 0.0 {
+            self.distribution
+                .eval_reflection(wo, wi)
+                .map_or(0.0, |(_, _, pdf)| pdf)
+        } else {
+            0.0
+        };
+        let base = if base_probability > 0.0 {
+            self.base.scatter_pdf(r_in, r_out, rec)
+        } else {
+            0.0
+        };
+        (coat_probability * coat + base_probability * base) / total
+    }
+
+    fn eval(&self, r_in: &Ray, r_out: &Ray, rec: &HitRecord) -> Color {
+        if !rec.front_face {
+            return self.base.eval(r_in, r_out, rec);
+        }
+        let (_, wo, wi) = local_directions(r_in, r_out, rec);
+        if wo.z <= 0.0 {
+            return color::BLACK;
+        }
+
+        // Normalize by the probability of a non-specular sample, which is what `eval` applies to.
+        let (coat_probability, base_probability) = self.non_specular_probabilities(wo.z);
+        let total = coat_probability + base_probability;
+        if total <= 0.0 {
+            return color::BLACK;
+        }
+        let coat = if coat_probability > 0.0 {
+            self.distribution
+                .eval_reflection(wo, wi)
+                .map_or(0.0, |(wm, value, _)| {
+                    value * fresnel_dielectric(wo.dot(wm), self.ior)
+                })
+        } else {
+            0.0
+        };
+        let base = if base_probability > 0.0 {
+            self.base.eval(r_in, r_out, rec) * self.base_weight(wo.z, wi.z)
+        } else {
+            color::BLACK
+        };
+        (Color::splat(coat) + base) / total
+    }
+
+    fn emit(&self, r_in: &Ray, rec: &HitRecord) -> Color {
+        let emitted = self.base.emit(r_in, rec);
+        if !rec.front_face || emitted == color::BLACK {
+            return emitted;
+        }
+        let cos = -r_in.dir.normalize().dot(rec.shading_normal);
+        emitted * (1.0 - fresnel_dielectric(cos, self.ior)) * self.transmittance(cos)
+    }
+
+    fn set_area(&mut self, area: f32) {
+        if let Some(base) = Arc::get_mut(&mut self.base) {
+            base.set_area(area);
+        }
+    }
+
+    fn power(&self, area: f32) -> f32 {
+        self.base.power(area)
+    }
+
+    fn is_specular(&self) -> bool {
+        self.distribution.is_smooth() && self.base.is_specular()
+    }
+
+    fn is_dispersive(&self) -> bool {
+        self.base.is_dispersive()
+    }
+}
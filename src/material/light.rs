@@ -1,32 +1,99 @@
+use std::f32::consts::PI;
 use std::sync::Arc;
 
 use crate::{
-    color::{self, Color},
+    color::{self, Color, luminance},
+    light::{LUMINOUS_EFFICACY, cone_falloff, hemisphere_fraction, ies::IesProfile},
     material::Material,
-    math::Point3,
+    math::{Point3, Ray, Vec3},
+    onb::ONB,
+    shape::HitRecord,
     texture::{Texture, solid_color::SolidColor},
 };
 
+/// The total power emitted by a light.
+#[derive(Clone, Copy)]
+pub enum Power {
+    /// The radiant flux in watts.
+    Watts(f32),
+
+    /// The luminous flux in lumens.
+    Lumens(f32),
+}
+
+impl Power {
+    /// Get the power in watts.
+    pub fn watts(self) -> f32 {
+        match self {
+            Self::Watts(watts) => watts,
+            Self::Lumens(lumens) => lumens / LUMINOUS_EFFICACY,
+        }
+    }
+}
+
+/// The distribution of the emitted radiance over directions relative to the surface normal.
+#[derive(Clone)]
+pub enum EmissionProfile {
+    /// The same radiance in all directions like an ideal diffuse emitter.
+    Diffuse,
+
+    /// The radiance inside a cone with half `angle` in radians around the normal, which fades out
+    /// over the `blend` fraction of the angle at the edge.
+    Spot { angle: f32, blend: f32 },
+
+    /// The radiance modulated by an IES profile aimed along the normal, whose zero horizontal
+    /// angle follows the partial derivative with respect to `u`.
+    Ies(Arc<IesProfile>),
+}
+
+impl EmissionProfile {
+    /// Get the fraction of the radiance emitted towards the unit direction `local` in the frame
+    /// whose z-axis is the normal and whose x-axis follows the partial derivative with respect
+    /// to `u`.
+    pub fn factor(&self, local: Vec3) -> f32 {
+        match self {
+            Self::Diffuse => 1.0,
+            Self::Spot { angle, blend } => cone_falloff(local.z, *angle, *blend),
+            Self::Ies(profile) => profile.relative(local),
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct Light {
     /// The texture representing the albedo of the material
     pub tex: Arc<dyn Texture>,
+
+    /// The scale of the emitted radiance.
+    pub strength: f32,
+
+    /// Whether the light emits from the back face as well as from the front face.
+    pub two_sided: bool,
+
+    /// The total power which is converted into radiance with the `area` of the emitter.
+    pub power: Option<Power>,
+
+    /// The surface area of the emitting shape.
+    pub area: Option<f32>,
+
+    /// The directional distribution of the emitted radiance.
+    pub profile: EmissionProfile,
+
+    /// The fraction of the flux of a diffuse emitter which is emitted with `profile`.
+    profile_flux: f32,
 }
 
 impl Default for Light {
     /// Create a default light material in white light.
     fn default() -> Self {
-        Self {
-            tex: Arc::new(SolidColor::new(color::WHITE)),
-        }
+        Self::from_texture(SolidColor::new(color::WHITE))
     }
 }
 
 impl Light {
     /// Create a light material from color.
     pub fn new(color: Color) -> Self {
-        Self {
-            tex: Arc::new(SolidColor::new(color)),
-        }
+        Self::from_texture(SolidColor::new(color))
     }
 
     /// Create a light material from texture.
@@ -34,12 +101,106 @@ impl Light {
     where
         T: Texture + 'static,
     {
-        Self { tex: Arc::new(tex) }
+        Self {
+            tex: Arc::new(tex),
+            strength: 1.0,
+            two_sided: false,
+            power: None,
+            area: None,
+            profile: EmissionProfile::Diffuse,
+            profile_flux: 1.0,
+        }
+    }
+
+    /// Set the scale of the emitted radiance.
+    pub const fn strength(mut self, strength: f32) -> Self {
+        self.strength = strength;
+        self
+    }
+
+    /// Set whether the light emits from both faces.
+    pub const fn two_sided(mut self, two_sided: bool) -> Self {
+        self.two_sided = two_sided;
+        self
+    }
+
+    /// Set the total power of the light. The color of the texture scales the emission on top of
+    /// the power, so a color with unit luminance emits exactly this power. The power is converted
+    /// into radiance with the area of the shape, which is set when attached to an `Object`.
+    pub const fn power(mut self, power: Power) -> Self {
+        self.power = Some(power);
+        self
+    }
+
+    /// Set the surface area of the emitting shape used to convert power into radiance. It is set
+    /// from the shape when attached to an `Object`.
+    pub const fn area(mut self, area: f32) -> Self {
+        self.area = Some(area);
+        self
+    }
+
+    /// Set the directional distribution of the emitted radiance.
+    pub fn profile(mut self, profile: EmissionProfile) -> Self {
+        self.profile_flux = match &profile {
+            EmissionProfile::Diffuse => 1.0,
+            _ => hemisphere_fraction(|local| profile.factor(local)),
+        };
+        self.profile = profile;
+        self
+    }
+
+    /// Get the factor which scales the texture color into emitted radiance. The power is ignored
+    /// without the area to convert it, which only happens for lights outside any `Object`.
+    pub fn radiance_scale(&self) -> f32 {
+        match (self.power, self.area) {
+            (Some(power), Some(area)) if area > 0.0 => {
+                // A diffuse emitter radiates pi times its radiance from each side of unit area.
+                let sides = if self.two_sided { 2.0 } else { 1.0 };
+                let flux = sides * PI * area * self.profile_flux;
+                if flux > 0.0 {
+                    self.strength * power.watts() / flux
+                } else {
+                    0.0
+                }
+            }
+            (Some(_), Some(_)) => 0.0,
+            (Some(_), None) | (None, _) => self.strength,
+        }
     }
 }
 
 impl Material for Light {
-    fn emit(&self, u: f32, v: f32, p: Point3) -> Color {
-        self.tex.sample(u, v, p)
+    fn set_area(&mut self, area: f32) {
+        self.area = Some(area);
+    }
+
+    fn power(&self, area: f32) -> f32 {
+        let sides = if self.two_sided { 2.0 } else { 1.0 };
+        let radiance = self.radiance_scale() * luminance(self.tex.sample(0.5, 0.5, Point3::ZERO));
+        radiance * sides * PI * area * self.profile_flux
+    }
+
+    fn emit(&self, r_in: &Ray, rec: &HitRecord) -> Color {
+        if !rec.front_face && !self.two_sided {
+            return color::BLACK;
+        }
+        let factor = match self.profile {
+            EmissionProfile::Diffuse => 1.0,
+            _ => {
+                // Mirror the directions leaving the back face onto the front face, in the frame
+                // around the outward normal.
+                let normal = if rec.front_face {
+                    rec.shading_normal
+                } else {
+                    -rec.shading_normal
+                };
+                let mut local = ONB::from_tangent(normal, rec.dpdu).local(-r_in.dir.normalize());
+                if !rec.front_face {
+                    local.z = -local.z;
+                }
+                self.profile.factor(local)
+            }
+        };
+        factor * self.radiance_scale() * self.tex.sample_hit(rec)
     }
 }
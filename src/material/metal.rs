@@ -1,57 +1,191 @@
 use std::sync::Arc;
 
 use crate::{
-    color::Color,
-    material::Material,
-    math::{Ray, Vec3, vec3::random_unit_vector},
+    color::{self, Color},
+    material::{
+        Material,
+        microfacet::{Ggx, fresnel_conductor, fresnel_schlick, local_directions, shading_frame},
+        thin_film::ThinFilm,
+    },
+    math::Ray,
+    shape::HitRecord,
     texture::{Texture, solid_color::SolidColor},
 };
 
+/// The Fresnel reflectance model of a metal.
+#[derive(Clone, Copy, Debug)]
+pub enum Fresnel {
+    /// Schlick's approximation where the texture gives the reflectance at normal incidence.
+    Schlick,
+
+    /// The exact reflectance of a conductor with complex index of refraction `eta + ik` given
+    /// for the red, green and blue channels, which is tinted by the texture.
+    Conductor { eta: Color, k: Color },
+}
+
+/// A microfacet conductor with the GGX distribution and height-correlated Smith masking.
 #[derive(Clone)]
 pub struct Metal {
     /// The texture of the material.
     pub tex: Arc<dyn Texture>,
 
-    /// The fuzziness of the reflected rays
-    pub fuzz: f32,
+    /// The distribution of microfacet normals.
+    pub distribution: Ggx,
+
+    /// The Fresnel reflectance model.
+    pub fresnel: Fresnel,
+
+    /// The optional thin film coating the surface.
+    pub thin_film: Option<ThinFilm>,
 }
 
 impl Metal {
-    /// Create a metal material from albedo and fuzz
-    pub fn new(color: Color, fuzz: f32) -> Self {
+    /// Create a metal material from reflectance at normal incidence and roughness.
+    pub fn new(color: Color, roughness: f32) -> Self {
         Self {
             tex: Arc::new(SolidColor::new(color)),
-            fuzz: fuzz.clamp(0.0, 1.0),
+            distribution: Ggx::new(roughness, roughness),
+            fresnel: Fresnel::Schlick,
+            thin_film: None,
         }
     }
 
     /// Create a metal material from texture.
-    pub fn from_texture<T>(tex: T, fuzz: f32) -> Self
+    pub fn from_texture<T>(tex: T, roughness: f32) -> Self
     where
         T: Texture + 'static,
     {
         Self {
             tex: Arc::new(tex),
-            fuzz,
+            distribution: Ggx::new(roughness, roughness),
+            fresnel: Fresnel::Schlick,
+            thin_film: None,
+        }
+    }
+
+    /// Create a conductor from complex index of refraction `eta + ik` and roughness.
+    pub fn conductor(eta: Color, k: Color, roughness: f32) -> Self {
+        Self {
+            tex: Arc::new(SolidColor::new(color::WHITE)),
+            distribution: Ggx::new(roughness, roughness),
+            fresnel: Fresnel::Conductor { eta, k },
+            thin_film: None,
+        }
+    }
+
+    /// Create a gold conductor.
+    pub fn gold(roughness: f32) -> Self {
+        Self::conductor(
+            Color::new(0.143, 0.374, 1.442),
+            Color::new(3.983, 2.386, 1.603),
+            roughness,
+        )
+    }
+
+    /// Create a copper conductor.
+    pub fn copper(roughness: f32) -> Self {
+        Self::conductor(
+            Color::new(0.200, 0.924, 1.102),
+            Color::new(3.912, 2.452, 2.142),
+            roughness,
+        )
+    }
+
+    /// Create an aluminium conductor.
+    pub fn aluminium(roughness: f32) -> Self {
+        Self::conductor(
+            Color::new(1.657, 0.880, 0.521),
+            Color::new(9.224, 6.270, 4.837),
+            roughness,
+        )
+    }
+
+    /// Create a silver conductor.
+    pub fn silver(roughness: f32) -> Self {
+        Self::conductor(
+            Color::new(0.155, 0.117, 0.138),
+            Color::new(4.828, 3.122, 2.147),
+            roughness,
+        )
+    }
+
+    /// Set different roughness along the tangent and the bitangent of the surface.
+    pub fn anisotropic(mut self, roughness_u: f32, roughness_v: f32) -> Self {
+        self.distribution = Ggx::new(roughness_u, roughness_v);
+        self
+    }
+
+    /// Coat the surface with a thin film producing iridescent interference colors.
+    pub fn thin_film(mut self, film: ThinFilm) -> Self {
+        self.thin_film = Some(film);
+        self
+    }
+
+    /// Get the Fresnel reflectance tinted by the texture at the intersection.
+    fn reflectance(&self, cos: f32, rec: &HitRecord) -> Color {
+        let tint = self.tex.sample_hit(rec);
+        match (self.fresnel, &self.thin_film) {
+            (Fresnel::Schlick, None) => fresnel_schlick(cos, tint),
+            (Fresnel::Conductor { eta, k }, None) => tint * fresnel_conductor(cos, eta, k),
+            (Fresnel::Schlick, Some(film)) => {
+                // Recover the real index of refraction giving the reflectance at normal incidence.
+                let f0 = tint.clamp(Color::ZERO, Color::splat(0.999)).powf(0.5);
+                let eta = (Color::ONE + f0) / (Color::ONE - f0);
+                film.reflectance(cos, 1.0, eta, Color::ZERO, rec)
+            }
+            (Fresnel::Conductor { eta, k }, Some(film)) => {
+                tint * film.reflectance(cos, 1.0, eta, k, rec)
+            }
         }
     }
 }
 
 impl Material for Metal {
-    fn scatter(&self, r_in: &Ray, rec: &crate::shape::HitRecord) -> Option<(Color, Ray)> {
-        let mut reflect_direction: Vec3 = r_in.dir.reflect(rec.normal);
-
-        // Normalize the `reflect_direction` or the direction change brought by `fuzz` will be very small.
-        reflect_direction = reflect_direction.normalize() + self.fuzz * random_unit_vector();
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Color, Ray)> {
+        let unit_direction = r_in.dir.normalize();
+        if self.distribution.is_smooth() {
+            let cos = -unit_direction.dot(rec.shading_normal);
+            let scatter = Ray::new(rec.p, unit_direction.reflect(rec.shading_normal), r_in.t);
+            return Some((self.reflectance(cos, rec), scatter));
+        }
 
-        let attenuation = self.tex.sample(rec.u, rec.v, rec.p);
-        let scatter = Ray::new(rec.p, reflect_direction, r_in.t);
+        // Reflect about a microfacet normal visible from the incident direction.
+        let frame = shading_frame(rec);
+        let wo = frame.local(-unit_direction);
+        if wo.z <= 0.0 {
+            return None;
+        }
+        let wi = self.distribution.sample_reflection(wo);
+        let scatter = Ray::new(rec.p, frame.transform(wi), r_in.t);
 
-        // After we add fuzz, we need to ensure the scattered ray is still in outer side of the surface of sphere
-        if scatter.dir.dot(rec.normal) > 0.0 {
-            Some((attenuation, scatter))
+        // The weight of the VNDF sample is F * G2 / G1.
+        let attenuation = if wi.z > 0.0 {
+            let wm = (wo + wi).normalize();
+            self.reflectance(wo.dot(wm), rec) * self.distribution.g(wo, wi)
+                / self.distribution.g1(wo)
         } else {
-            None
-        }
+            color::BLACK
+        };
+        Some((attenuation, scatter))
+    }
+
+    fn scatter_pdf(&self, r_in: &Ray, r_out: &Ray, rec: &HitRecord) -> f32 {
+        let (_, wo, wi) = local_directions(r_in, r_out, rec);
+        self.distribution
+            .eval_reflection(wo, wi)
+            .map_or(0.0, |(_, _, pdf)| pdf)
+    }
+
+    fn eval(&self, r_in: &Ray, r_out: &Ray, rec: &HitRecord) -> Color {
+        let (_, wo, wi) = local_directions(r_in, r_out, rec);
+        self.distribution
+            .eval_reflection(wo, wi)
+            .map_or(color::BLACK, |(wm, value, _)| {
+                self.reflectance(wo.dot(wm), rec) * value
+            })
+    }
+
+    fn is_specular(&self) -> bool {
+        self.distribution.is_smooth()
     }
 }
@@ -0,0 +1,286 @@
+use std::f32::consts::PI;
+
+use crate::{
+    color::Color,
+    math::{Ray, Vec3, random},
+    onb::ONB,
+    shape::HitRecord,
+};
+
+/// Roughness below which the surface is treated as perfectly smooth.
+const SMOOTH_ALPHA: f32 = 1e-3;
+
+/// The anisotropic GGX (Trowbridge-Reitz) distribution of microfacet normals.
+///
+/// Directions are expressed in the local shading frame whose z-axis is the surface normal and
+/// whose x-axis is the tangent along which `alpha_x` is measured.
+#[derive(Clone, Copy, Debug)]
+pub struct Ggx {
+    /// The roughness along the tangent.
+    pub alpha_x: f32,
+
+    /// The roughness along the bitangent.
+    pub alpha_y: f32,
+}
+
+impl Ggx {
+    /// Create a distribution from the perceptual roughness in [0, 1] along the tangent and the
+    /// bitangent, which is squared to get the distribution width.
+    pub fn new(roughness_x: f32, roughness_y: f32) -> Self {
+        Self {
+            alpha_x: Self::roughness_to_alpha(roughness_x),
+            alpha_y: Self::roughness_to_alpha(roughness_y),
+        }
+    }
+
+    /// Map a perceptual roughness to the width of the distribution.
+    pub fn roughness_to_alpha(roughness: f32) -> f32 {
+        let roughness = roughness.clamp(0.0, 1.0);
+        roughness * roughness
+    }
+
+    /// Whether the distribution is narrow enough to be treated as a perfect mirror.
+    pub fn is_smooth(&self) -> bool {
+        self.alpha_x.max(self.alpha_y) < SMOOTH_ALPHA
+    }
+
+    /// Get the density of microfacets with normal `wm`.
+    pub fn d(&self, wm: Vec3) -> f32 {
+        if wm.z <= 0.0 {
+            return 0.0;
+        }
+        let x = wm.x / self.alpha_x;
+        let y = wm.y / self.alpha_y;
+        let t = x * x + y * y + wm.z * wm.z;
+        1.0 / (PI * self.alpha_x * self.alpha_y * t * t)
+    }
+
+    /// Get the Smith auxiliary function of direction `w`.
+    pub fn lambda(&self, w: Vec3) -> f32 {
+        if w.z == 0.0 {
+            return f32::INFINITY;
+        }
+        let ax = self.alpha_x * w.x;
+        let ay = self.alpha_y * w.y;
+        let tan2 = (ax * ax + ay * ay) / (w.z * w.z);
+        ((1.0 + tan2).sqrt() - 1.0) / 2.0
+    }
+
+    /// Get the fraction of microfacets visible from direction `w`.
+    pub fn g1(&self, w: Vec3) -> f32 {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    /// Get the height-correlated fraction of microfacets visible from both `wo` and `wi`.
+    pub fn g(&self, wo: Vec3, wi: Vec3) -> f32 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    /// Sample a microfacet normal from the distribution of normals visible from direction `w`.
+    pub fn sample_wm(&self, w: Vec3) -> Vec3 {
+        // Stretch the view direction so the distribution becomes an hemisphere of unit roughness.
+        let mut wh = Vec3::new(self.alpha_x * w.x, self.alpha_y * w.y, w.z).normalize();
+        if wh.z < 0.0 {
+            wh = -wh;
+        }
+        let t1 = if wh.z < 0.99999 {
+            Vec3::Z.cross(wh).normalize()
+        } else {
+            Vec3::X
+        };
+        let t2 = wh.cross(t1);
+
+        // Sample the projected disk and warp it onto the visible half.
+        let r = random().sqrt();
+        let phi = 2.0 * PI * random();
+        let p1 = r * phi.cos();
+        let h = (1.0 - p1 * p1).sqrt();
+        let s = 0.5 * (1.0 + wh.z);
+        let p2 = (1.0 - s) * h + s * r * phi.sin();
+        let pz = (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt();
+        let nh = p1 * t1 + p2 * t2 + pz * wh;
+
+        // Unstretch back to the distribution.
+        Vec3::new(self.alpha_x * nh.x, self.alpha_y * nh.y, nh.z.max(1e-6)).normalize()
+    }
+
+    /// Get the probability density of sampling the microfacet normal `wm` by `sample_wm`.
+    pub fn pdf(&self, w: Vec3, wm: Vec3) -> f32 {
+        if w.z == 0.0 {
+            return 0.0;
+        }
+        self.g1(w) / w.z.abs() * self.d(wm) * w.dot(wm).abs()
+    }
+
+    /// Sample a direction reflected about a visible microfacet normal from `wo`, which could go
+    /// below the surface and then carries no energy.
+    pub fn sample_reflection(&self, wo: Vec3) -> Vec3 {
+        (-wo).reflect(self.sample_wm(wo))
+    }
+
+    /// Evaluate the reflection from `wo` to `wi` without the Fresnel term. Return the microfacet
+    /// normal, the scattering function multiplied by the cosine term and the probability density
+    /// of `sample_reflection`.
+    pub fn eval_reflection(&self, wo: Vec3, wi: Vec3) -> Option<(Vec3, f32, f32)> {
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return None;
+        }
+        let wm = (wo + wi).normalize();
+        let value = self.d(wm) * self.g(wo, wi) / (4.0 * wo.z);
+        let pdf = self.pdf(wo, wm) / (4.0 * wo.dot(wm));
+        Some((wm, value, pdf))
+    }
+
+    /// Get the generalized half vector of the refraction from `wo` to `wi` facing the near side,
+    /// where `eta` is the index of refraction of the far side over the near side. Return `None`
+    /// if the pair is not a valid refraction through a microfacet.
+    pub fn refraction_half_vector(wo: Vec3, wi: Vec3, eta: f32) -> Option<Vec3> {
+        let mut wm = (wo + wi * eta).normalize();
+        if wm.z < 0.0 {
+            wm = -wm;
+        }
+        (wm.is_finite() && wm.dot(wo) > 0.0 && wm.dot(wi) < 0.0).then_some(wm)
+    }
+
+    /// Evaluate the refraction from `wo` to `wi` without the Fresnel term. Return the microfacet
+    /// normal, the scattering function multiplied by the cosine term and the probability density
+    /// of refracting through a visible microfacet normal.
+    ///
+    /// The scaling of radiance by eta squared is left out, since it cancels out between entering
+    /// and leaving a closed object.
+    pub fn eval_refraction(&self, wo: Vec3, wi: Vec3, eta: f32) -> Option<(Vec3, f32, f32)> {
+        if wo.z <= 0.0 || wi.z >= 0.0 {
+            return None;
+        }
+        let wm = Self::refraction_half_vector(wo, wi, eta)?;
+        let denom = (wi.dot(wm) + wo.dot(wm) / eta).powi(2);
+        let dwm_dwi = wi.dot(wm).abs() / denom;
+        let value = self.d(wm) * self.g(wo, wi) * wo.dot(wm) * dwm_dwi / wo.z;
+        let pdf = self.pdf(wo, wm) * dwm_dwi;
+        Some((wm, value, pdf))
+    }
+}
+
+/// The Charlie distribution of fibers for sheen, with the visibility fitted by Estevez and Kulla.
+///
+/// Directions are expressed in the local shading frame whose z-axis is the surface normal.
+#[derive(Clone, Copy, Debug)]
+pub struct Charlie {
+    /// The width of the distribution.
+    pub alpha: f32,
+}
+
+impl Charlie {
+    /// Create a distribution from the perceptual roughness in [0, 1], which is squared to get the
+    /// distribution width.
+    pub fn new(roughness: f32) -> Self {
+        Self {
+            alpha: Ggx::roughness_to_alpha(roughness).max(SMOOTH_ALPHA),
+        }
+    }
+
+    /// Get the density of fibers with normal `wm`.
+    pub fn d(&self, wm: Vec3) -> f32 {
+        let sin = (1.0 - wm.z * wm.z).max(0.0).sqrt();
+        let inv_alpha = 1.0 / self.alpha;
+        (2.0 + inv_alpha) * sin.powf(inv_alpha) / (2.0 * PI)
+    }
+
+    /// Get the fitted curve from which the auxiliary function is built.
+    fn l(&self, x: f32) -> f32 {
+        let t = (1.0 - self.alpha).powi(2);
+        let lerp = |a: f32, b: f32| a + (b - a) * t;
+        let a = lerp(21.5473, 25.3245);
+        let b = lerp(3.82987, 3.32435);
+        let c = lerp(0.19823, 0.16801);
+        let d = lerp(-1.97760, -1.27393);
+        let e = lerp(-4.32054, -4.85967);
+        a / (1.0 + b * x.powf(c)) + d * x + e
+    }
+
+    /// Get the auxiliary function of direction `w`.
+    pub fn lambda(&self, w: Vec3) -> f32 {
+        let cos = w.z.abs();
+        if cos < 0.5 {
+            self.l(cos).exp()
+        } else {
+            (2.0 * self.l(0.5) - self.l(1.0 - cos)).exp()
+        }
+    }
+
+    /// Get the fraction of fibers visible from both `wo` and `wi`, where the shadowing towards
+    /// `wi` is softened near the terminator.
+    pub fn g(&self, wo: Vec3, wi: Vec3) -> f32 {
+        let lambda_i = self.lambda(wi).powf(1.0 + 2.0 * (1.0 - wi.z).powi(8));
+        1.0 / (1.0 + self.lambda(wo) + lambda_i)
+    }
+
+    /// Evaluate the sheen from `wo` to `wi` multiplied by the cosine term.
+    pub fn eval(&self, wo: Vec3, wi: Vec3) -> f32 {
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return 0.0;
+        }
+        let wm = (wo + wi).normalize();
+        self.d(wm) * self.g(wo, wi) / (4.0 * wo.z)
+    }
+}
+
+/// Get the local directions of the incident and the scattered ray in the tangent frame of the
+/// intersection, together with the frame.
+pub fn local_directions(r_in: &Ray, r_out: &Ray, rec: &HitRecord) -> (ONB, Vec3, Vec3) {
+    let frame = shading_frame(rec);
+    let wo = frame.local(-r_in.dir.normalize());
+    let wi = frame.local(r_out.dir.normalize());
+    (frame, wo, wi)
+}
+
+/// Get the tangent frame of the intersection whose z-axis is the shading normal and whose x-axis
+/// follows the partial derivative with respect to `u`.
+pub fn shading_frame(rec: &HitRecord) -> ONB {
+    ONB::from_tangent(rec.shading_normal, rec.dpdu)
+}
+
+/// Get the Fresnel reflectance of a conductor with complex index of refraction `eta + ik` at the
+/// incident angle with cosine `cos`, for each color channel.
+pub fn fresnel_conductor(cos: f32, eta: Color, k: Color) -> Color {
+    let channel = |eta: f32, k: f32| {
+        let cos2 = cos.clamp(0.0, 1.0).powi(2);
+        let sin2 = 1.0 - cos2;
+        let t0 = eta * eta - k * k - sin2;
+        let a2b2 = (t0 * t0 + 4.0 * eta * eta * k * k).sqrt();
+        let a = ((a2b2 + t0) / 2.0).max(0.0).sqrt();
+        let t1 = a2b2 + cos2;
+        let t2 = 2.0 * a * cos2.sqrt();
+        let rs = (t1 - t2) / (t1 + t2);
+        let t3 = cos2 * a2b2 + sin2 * sin2;
+        let t4 = t2 * sin2;
+        let rp = rs * (t3 - t4) / (t3 + t4);
+        (rs + rp) / 2.0
+    };
+    Color::new(
+        channel(eta.x, k.x),
+        channel(eta.y, k.y),
+        channel(eta.z, k.z),
+    )
+}
+
+/// Get the Schlick approximation of the Fresnel reflectance from the reflectance `f0` at normal
+/// incidence.
+pub fn fresnel_schlick(cos: f32, f0: Color) -> Color {
+    f0 + (Color::ONE - f0) * (1.0 - cos.clamp(0.0, 1.0)).powi(5)
+}
+
+/// Get the exact Fresnel reflectance of a dielectric interface at the incident angle with cosine
+/// `cos`, where `eta` is the index of refraction of the transmitted side over the incident side.
+pub fn fresnel_dielectric(cos: f32, eta: f32) -> f32 {
+    let cos_i = cos.clamp(0.0, 1.0);
+    let sin2_t = (1.0 - cos_i * cos_i) / (eta * eta);
+    if sin2_t >= 1.0 {
+        // Total internal reflection.
+        return 1.0;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    let r_parl = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    let r_perp = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    (r_parl * r_parl + r_perp * r_perp) / 2.0
+}
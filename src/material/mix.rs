@@ -0,0 +1,158 @@
+use std::ops::{Add, Mul};
+use std::sync::Arc;
+
+use crate::{
+    color::Color,
+    material::Material,
+    math::{Point3, Ray, random},
+    shape::HitRecord,
+    texture::{Texture, solid_color::SolidColor},
+};
+
+/// A blend of two materials whose weight is driven by a texture, e.g. rust on metal through an
+/// image mask.
+///
+/// Scattering chooses one material by the weight at the intersection. A sample of a specular
+/// material follows it alone, while the scattering function and its density blend the
+/// non-specular materials, so that light sampling stays consistent for their samples.
+#[derive(Clone)]
+pub struct MixMaterial {
+    /// The material chosen where the weight is zero.
+    pub first: Arc<dyn Material>,
+
+    /// The material chosen where the weight is one.
+    pub second: Arc<dyn Material>,
+
+    /// The weight of the second material, read from the red channel.
+    pub weight: Arc<dyn Texture>,
+}
+
+impl MixMaterial {
+    /// Create a blend of two materials from the constant weight of the second one.
+    pub fn new<A, B>(first: A, second: B, weight: f32) -> Self
+    where
+        A: Material + 'static,
+        B: Material + 'static,
+    {
+        Self::from_texture(first, second, SolidColor::new(Color::splat(weight)))
+    }
+
+    /// Create a blend of two materials from the weight texture of the second one.
+    pub fn from_texture<A, B, T>(first: A, second: B, weight: T) -> Self
+    where
+        A: Material + 'static,
+        B: Material + 'static,
+        T: Texture + 'static,
+    {
+        Self {
+            first: Arc::new(first),
+            second: Arc::new(second),
+            weight: Arc::new(weight),
+        }
+    }
+
+    /// Get the weight of the second material at the intersection.
+    fn weight(&self, rec: &HitRecord) -> f32 {
+        self.weight.sample_hit(rec).x.clamp(0.0, 1.0)
+    }
+
+    /// Blend a quantity of the two materials at the intersection.
+    fn blend<T, F>(&self, rec: &HitRecord, f: F) -> T
+    where
+        T: Mul<f32, Output = T> + Add<Output = T>,
+        F: Fn(&dyn Material) -> T,
+    {
+        let weight = self.weight(rec);
+        f(self.first.as_ref()) * (1.0 - weight) + f(self.second.as_ref()) * weight
+    }
+
+    /// Blend a quantity of the non-specular materials at the intersection, normalized by their
+    /// total weight since it only applies once a non-specular material has been chosen.
+    fn blend_non_specular<T, F>(&self, rec: &HitRecord, f: F) -> T
+    where
+        T: Mul<f32, Output = T> + Add<Output = T> + Default,
+        F: Fn(&dyn Material) -> T,
+    {
+        let weight = self.weight(rec);
+        let materials = [
+            (self.first.as_ref(), 1.0 - weight),
+            (self.second.as_ref(), weight),
+        ];
+        let total: f32 = materials
+            .iter()
+            .filter(|(material, _)| !material.is_specular())
+            .map(|&(_, weight)| weight)
+            .sum();
+
+        // Skip the materials which are not chosen, whose values may not even be finite.
+        let mut sum = T::default();
+        for (material, weight) in materials {
+            if weight > 0.0 && !material.is_specular() {
+                sum = sum + f(material) * (weight / total);
+            }
+        }
+        sum
+    }
+}
+
+impl Material for MixMaterial {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Color, Ray)> {
+        let (attenuation, scatter, _) = self.sample_scatter(r_in, rec)?;
+        Some((attenuation, scatter))
+    }
+
+    fn sample_scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Color, Ray, bool)> {
+        let chosen = if random() < self.weight(rec) {
+            &self.second
+        } else {
+            &self.first
+        };
+        let (attenuation, scatter, specular) = chosen.sample_scatter(r_in, rec)?;
+        if specular {
+            return Some((attenuation, scatter, true));
+        }
+
+        // The direction is sampled from the blended density, so the attenuation is recomputed
+        // from the blended scattering function.
+        let pdf = self.scatter_pdf(r_in, &scatter, rec);
+        let attenuation = if pdf > 0.0 {
+            self.eval(r_in, &scatter, rec) / pdf
+        } else {
+            Color::ZERO
+        };
+        Some((attenuation, scatter, false))
+    }
+
+    fn scatter_pdf(&self, r_in: &Ray, r_out: &Ray, rec: &HitRecord) -> f32 {
+        self.blend_non_specular(rec, |material| material.scatter_pdf(r_in, r_out, rec))
+    }
+
+    fn eval(&self, r_in: &Ray, r_out: &Ray, rec: &HitRecord) -> Color {
+        self.blend_non_specular(rec, |material| material.eval(r_in, r_out, rec))
+    }
+
+    fn emit(&self, r_in: &Ray, rec: &HitRecord) -> Color {
+        self.blend(rec, |material| material.emit(r_in, rec))
+    }
+
+    fn set_area(&mut self, area: f32) {
+        for material in [&mut self.first, &mut self.second] {
+            if let Some(material) = Arc::get_mut(material) {
+                material.set_area(area);
+            }
+        }
+    }
+
+    fn power(&self, area: f32) -> f32 {
+        let weight = self.weight.sample(0.5, 0.5, Point3::ZERO).x.clamp(0.0, 1.0);
+        self.first.power(area) * (1.0 - weight) + self.second.power(area) * weight
+    }
+
+    fn is_specular(&self) -> bool {
+        self.first.is_specular() && self.second.is_specular()
+    }
+
+    fn is_dispersive(&self) -> bool {
+        self.first.is_dispersive() || self.second.is_dispersive()
+    }
+}
@@ -0,0 +1,144 @@
+use std::sync::Arc;
+
+use crate::{
+    color::Color,
+    material::Material,
+    math::{Ray, Vec3},
+    onb::ONB,
+    shape::HitRecord,
+    texture::Texture,
+};
+
+/// The offset in texture coordinates of the finite differences of bump maps.
+const BUMP_DELTA: f32 = 5e-4;
+
+/// The detail map perturbing the shading normal.
+#[derive(Clone)]
+pub enum NormalMap {
+    /// A tangent-space normal map whose colors in [0, 1] encode the normal along the tangent,
+    /// the bitangent and the normal, e.g. loaded by `ImageTexture::load_data`.
+    Tangent(Arc<dyn Texture>),
+
+    /// A height field read from the red channel and multiplied by `scale`, whose finite
+    /// differences tilt the normal.
+    Bump {
+        height: Arc<dyn Texture>,
+        scale: f32,
+    },
+}
+
+/// A material whose shading normal is perturbed by a normal map or a bump map, which adds
+/// surface detail without changing the geometry.
+#[derive(Clone)]
+pub struct NormalMapped {
+    /// The material shaded with the perturbed normal.
+    pub material: Arc<dyn Material>,
+
+    /// The detail map.
+    pub map: NormalMap,
+}
+
+impl NormalMapped {
+    /// Create a material perturbed by a tangent-space normal map.
+    pub fn normal_map<M, T>(material: M, map: T) -> Self
+    where
+        M: Material + 'static,
+        T: Texture + 'static,
+    {
+        Self {
+            material: Arc::new(material),
+            map: NormalMap::Tangent(Arc::new(map)),
+        }
+    }
+
+    /// Create a material perturbed by a bump map from height texture and scale.
+    pub fn bump_map<M, T>(material: M, height: T, scale: f32) -> Self
+    where
+        M: Material + 'static,
+        T: Texture + 'static,
+    {
+        Self {
+            material: Arc::new(material),
+            map: NormalMap::Bump {
+                height: Arc::new(height),
+                scale,
+            },
+        }
+    }
+
+    /// Get the intersection with the perturbed shading normal.
+    fn perturb(&self, rec: &HitRecord) -> HitRecord {
+        let mut rec = rec.clone();
+        let normal = match &self.map {
+            NormalMap::Tangent(map) => {
+                let frame = ONB::from_tangent(rec.shading_normal, rec.dpdu);
+                let mut local = 2.0 * map.sample_hit(&rec) - Color::ONE;
+
+                // Keep the bitangent along increasing `v` when the face is seen from behind.
+                if frame.local(rec.dpdv).y < 0.0 {
+                    local.y = -local.y;
+                }
+                frame.transform(local)
+            }
+            NormalMap::Bump { height, scale } => {
+                let height = |u: f32, v: f32, p: Vec3| height.sample(u, v, p).x * scale;
+                let h = height(rec.u, rec.v, rec.p);
+                let h_u = height(rec.u + BUMP_DELTA, rec.v, rec.p + BUMP_DELTA * rec.dpdu);
+                let h_v = height(rec.u, rec.v + BUMP_DELTA, rec.p + BUMP_DELTA * rec.dpdv);
+                rec.dpdu += (h_u - h) / BUMP_DELTA * rec.shading_normal;
+                rec.dpdv += (h_v - h) / BUMP_DELTA * rec.shading_normal;
+                rec.dpdu.cross(rec.dpdv)
+            }
+        }
+        .normalize_or_zero();
+
+        if normal != Vec3::ZERO {
+            rec.shading_normal = if normal.dot(rec.normal) < 0.0 {
+                -normal
+            } else {
+                normal
+            };
+        }
+        rec
+    }
+}
+
+impl Material for NormalMapped {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Color, Ray)> {
+        self.material.scatter(r_in, &self.perturb(rec))
+    }
+
+    fn emit(&self, r_in: &Ray, rec: &HitRecord) -> Color {
+        self.material.emit(r_in, &self.perturb(rec))
+    }
+
+    fn scatter_pdf(&self, r_in: &Ray, r_out: &Ray, rec: &HitRecord) -> f32 {
+        self.material.scatter_pdf(r_in, r_out, &self.perturb(rec))
+    }
+
+    fn eval(&self, r_in: &Ray, r_out: &Ray, rec: &HitRecord) -> Color {
+        self.material.eval(r_in, r_out, &self.perturb(rec))
+    }
+
+    fn set_area(&mut self, area: f32) {
+        if let Some(material) = Arc::get_mut(&mut self.material) {
+            material.set_area(area);
+        }
+    }
+
+    fn power(&self, area: f32) -> f32 {
+        self.material.power(area)
+    }
+
+    fn is_specular(&self) -> bool {
+        self.material.is_specular()
+    }
+
+    fn sample_scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Color, Ray, bool)> {
+        self.material.sample_scatter(r_in, &self.perturb(rec))
+    }
+
+    fn is_dispersive(&self) -> bool {
+        self.material.is_dispersive()
+    }
+}
@@ -0,0 +1,117 @@
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use crate::{
+    color::{self, Color},
+    material::{
+        Material,
+        microfacet::{local_directions, shading_frame},
+        sheen::Sheen,
+    },
+    math::{Ray, vec3::random_cosine_weight_on_hemisphere},
+    shape::HitRecord,
+    texture::{Texture, solid_color::SolidColor},
+};
+
+/// A rough diffuse material made of Lambertian V-shaped microfacets, which looks flatter and
+/// more retro-reflective than `Lambertian` like clay, concrete and fabric.
+#[derive(Clone)]
+pub struct OrenNayar {
+    /// The texture of the material.
+    pub tex: Arc<dyn Texture>,
+
+    /// The standard deviation of the microfacet slope angle in degrees, where zero is
+    /// Lambertian.
+    pub sigma: f32,
+
+    /// The optional sheen over the diffuse, for cloth.
+    pub sheen: Option<Sheen>,
+}
+
+impl OrenNayar {
+    /// Create an Oren-Nayar material from albedo and roughness angle in degrees.
+    pub fn new(albedo: Color, sigma: f32) -> Self {
+        Self::from_texture(SolidColor::new(albedo), sigma)
+    }
+
+    /// Create an Oren-Nayar material from texture and roughness angle in degrees.
+    pub fn from_texture<T>(tex: T, sigma: f32) -> Self
+    where
+        T: Texture + 'static,
+    {
+        Self {
+            tex: Arc::new(tex),
+            sigma,
+            sheen: None,
+        }
+    }
+
+    /// Add a sheen over the diffuse. The diffuse is scaled down by the light the sheen reflects,
+    /// so that the sum does not reflect more than it receives.
+    pub fn sheen(mut self, sheen: Sheen) -> Self {
+        self.sheen = Some(sheen);
+        self
+    }
+
+    /// Evaluate the diffuse reflection from `wo` to `wi` multiplied by the cosine term, for
+    /// unit albedo.
+    fn diffuse(&self, wo: f32, wi: f32, cos_phi: f32) -> f32 {
+        let sigma2 = self.sigma.to_radians().powi(2);
+        let a = 1.0 - sigma2 / (2.0 * (sigma2 + 0.33));
+        let b = 0.45 * sigma2 / (sigma2 + 0.09);
+
+        // Take alpha as the larger polar angle and beta as the smaller one.
+        let sin_o = (1.0 - wo * wo).max(0.0).sqrt();
+        let sin_i = (1.0 - wi * wi).max(0.0).sqrt();
+        let (sin_alpha, tan_beta) = if wi > wo {
+            (sin_o, sin_i / wi)
+        } else {
+            (sin_i, sin_o / wo)
+        };
+        (a + b * cos_phi.max(0.0) * sin_alpha * tan_beta) * wi / PI
+    }
+}
+
+impl Material for OrenNayar {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Color, Ray)> {
+        let frame = shading_frame(rec);
+        let scatter = Ray::new(
+            rec.p,
+            frame.transform(random_cosine_weight_on_hemisphere()),
+            r_in.t,
+        );
+        let pdf = self.scatter_pdf(r_in, &scatter, rec);
+        let attenuation = if pdf > 0.0 {
+            self.eval(r_in, &scatter, rec) / pdf
+        } else {
+            color::BLACK
+        };
+        Some((attenuation, scatter))
+    }
+
+    fn scatter_pdf(&self, _r_in: &Ray, r_out: &Ray, rec: &HitRecord) -> f32 {
+        let cos = rec.shading_normal.dot(r_out.dir.normalize());
+        cos.max(0.0) / PI
+    }
+
+    fn eval(&self, r_in: &Ray, r_out: &Ray, rec: &HitRecord) -> Color {
+        let (_, wo, wi) = local_directions(r_in, r_out, rec);
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return color::BLACK;
+        }
+
+        // The cosine of the azimuthal angle between the directions.
+        let projected = (wo.x * wo.x + wo.y * wo.y) * (wi.x * wi.x + wi.y * wi.y);
+        let cos_phi = if projected > 0.0 {
+            (wo.x * wi.x + wo.y * wi.y) / projected.sqrt()
+        } else {
+            0.0
+        };
+        let diffuse = self.tex.sample_hit(rec) * self.diffuse(wo.z, wi.z, cos_phi);
+        let Some(sheen) = &self.sheen else {
+            return diffuse;
+        };
+        let color = sheen.tex.sample_hit(rec);
+        diffuse * sheen.base_weight(color, wo.z, wi.z) + color * sheen.distribution.eval(wo, wi)
+    }
+}
@@ -0,0 +1,531 @@
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use crate::{
+    color::{self, Color, luminance},
+    material::{
+        Material,
+        microfacet::{Ggx, fresnel_dielectric, fresnel_schlick, local_directions, shading_frame},
+    },
+    math::{Ray, Vec3, random, vec3::random_cosine_weight_on_hemisphere},
+    shape::HitRecord,
+    texture::{Texture, solid_color::SolidColor},
+};
+
+/// The roughness below which the rough specular lobes are clamped to keep them evaluable, unless
+/// they are smooth enough to become a perfect mirror and refraction.
+const MIN_ROUGHNESS: f32 = 0.05;
+
+/// The reflectance at normal incidence of the clearcoat layer with index of refraction 1.5.
+const CLEARCOAT_F0: f32 = 0.04;
+
+/// A Disney-style principled material which blends diffuse, sheen, specular, clearcoat and
+/// transmission lobes from a few artist-friendly parameters, each driven by a texture.
+///
+/// Scalar parameters are read from the red channel of their textures.
+#[derive(Clone)]
+pub struct Principled {
+    /// The base color of the diffuse, metallic and transmission lobes.
+    pub base_color: Arc<dyn Texture>,
+
+    /// Blends between a dielectric (0) and a metal (1).
+    pub metallic: Arc<dyn Texture>,
+
+    /// The perceptual roughness of the specular and transmission lobes.
+    pub roughness: Arc<dyn Texture>,
+
+    /// The specular reflectance of dielectrics, where 0.5 is 4% at normal incidence.
+    pub specular: Arc<dyn Texture>,
+
+    /// Tints the specular reflectance of dielectrics towards the base color.
+    pub specular_tint: Arc<dyn Texture>,
+
+    /// The strength of the retro-reflective sheen at grazing angles.
+    pub sheen: Arc<dyn Texture>,
+
+    /// Tints the sheen towards the base color.
+    pub sheen_tint: Arc<dyn Texture>,
+
+    /// The strength of the clearcoat layer.
+    pub clearcoat: Arc<dyn Texture>,
+
+    /// The perceptual roughness of the clearcoat layer.
+    pub clearcoat_roughness: Arc<dyn Texture>,
+
+    /// Blends between an opaque (0) and a transmissive (1) dielectric.
+    pub transmission: Arc<dyn Texture>,
+
+    /// The index of refraction of the transmission lobe.
+    pub ior: f32,
+
+    /// The emitted radiance from the front face.
+    pub emission: Arc<dyn Texture>,
+}
+
+impl Default for Principled {
+    fn default() -> Self {
+        Self::from_texture(SolidColor::new(Color::splat(0.8)))
+    }
+}
+
+impl Principled {
+    /// Create a principled material from base color.
+    pub fn new(base_color: Color) -> Self {
+        Self::from_texture(SolidColor::new(base_color))
+    }
+
+    /// Create a principled material from base color texture.
+    pub fn from_texture<T>(tex: T) -> Self
+    where
+        T: Texture + 'static,
+    {
+        let constant = |value: f32| Arc::new(SolidColor::new(Color::splat(value)));
+        Self {
+            base_color: Arc::new(tex),
+            metallic: constant(0.0),
+            roughness: constant(0.5),
+            specular: constant(0.5),
+            specular_tint: constant(0.0),
+            sheen: constant(0.0),
+            sheen_tint: constant(0.5),
+            clearcoat: constant(0.0),
+            clearcoat_roughness: constant(0.03),
+            transmission: constant(0.0),
+            ior: 1.5,
+            emission: Arc::new(SolidColor::new(color::BLACK)),
+        }
+    }
+
+    /// Set the metallic parameter.
+    pub fn metallic(self, metallic: f32) -> Self {
+        self.metallic_texture(SolidColor::new(Color::splat(metallic)))
+    }
+
+    /// Set the metallic parameter from texture.
+    pub fn metallic_texture<T>(mut self, tex: T) -> Self
+    where
+        T: Texture + 'static,
+    {
+        self.metallic = Arc::new(tex);
+        self
+    }
+
+    /// Set the roughness parameter.
+    pub fn roughness(self, roughness: f32) -> Self {
+        self.roughness_texture(SolidColor::new(Color::splat(roughness)))
+    }
+
+    /// Set the roughness parameter from texture.
+    pub fn roughness_texture<T>(mut self, tex: T) -> Self
+    where
+        T: Texture + 'static,
+    {
+        self.roughness = Arc::new(tex);
+        self
+    }
+
+    /// Set the specular parameter.
+    pub fn specular(self, specular: f32) -> Self {
+        self.specular_texture(SolidColor::new(Color::splat(specular)))
+    }
+
+    /// Set the specular parameter from texture.
+    pub fn specular_texture<T>(mut self, tex: T) -> Self
+    where
+        T: Texture + 'static,
+    {
+        self.specular = Arc::new(tex);
+        self
+    }
+
+    /// Set the specular tint parameter.
+    pub fn specular_tint(self, specular_tint: f32) -> Self {
+        self.specular_tint_texture(SolidColor::new(Color::splat(specular_tint)))
+    }
+
+    /// Set the specular tint parameter from texture.
+    pub fn specular_tint_texture<T>(mut self, tex: T) -> Self
+    where
+        T: Texture + 'static,
+    {
+        self.specular_tint = Arc::new(tex);
+        self
+    }
+
+    /// Set the sheen parameter.
+    pub fn sheen(self, sheen: f32) -> Self {
+        self.sheen_texture(SolidColor::new(Color::splat(sheen)))
+    }
+
+    /// Set the sheen parameter from texture.
+    pub fn sheen_texture<T>(mut self, tex: T) -> Self
+    where
+        T: Texture + 'static,
+    {
+        self.sheen = Arc::new(tex);
+        self
+    }
+
+    /// Set the sheen tint parameter.
+    pub fn sheen_tint(self, sheen_tint: f32) -> Self {
+        self.sheen_tint_texture(SolidColor::new(Color::splat(sheen_tint)))
+    }
+
+    /// Set the sheen tint parameter from texture.
+    pub fn sheen_tint_texture<T>(mut self, tex: T) -> Self
+    where
+        T: Texture + 'static,
+    {
+        self.sheen_tint = Arc::new(tex);
+        self
+    }
+
+    /// Set the clearcoat strength and roughness parameters.
+    pub fn clearcoat(self, clearcoat: f32, roughness: f32) -> Self {
+        self.clearcoat_texture(
+            SolidColor::new(Color::splat(clearcoat)),
+            SolidColor::new(Color::splat(roughness)),
+        )
+    }
+
+    /// Set the clearcoat strength and roughness parameters from textures.
+    pub fn clearcoat_texture<T, R>(mut self, tex: T, roughness: R) -> Self
+    where
+        T: Texture + 'static,
+        R: Texture + 'static,
+    {
+        self.clearcoat = Arc::new(tex);
+        self.clearcoat_roughness = Arc::new(roughness);
+        self
+    }
+
+    /// Set the transmission parameter.
+    pub fn transmission(self, transmission: f32) -> Self {
+        self.transmission_texture(SolidColor::new(Color::splat(transmission)))
+    }
+
+    /// Set the transmission parameter from texture.
+    pub fn transmission_texture<T>(mut self, tex: T) -> Self
+    where
+        T: Texture + 'static,
+    {
+        self.transmission = Arc::new(tex);
+        self
+    }
+
+    /// Set the index of refraction of the transmission lobe.
+    pub const fn ior(mut self, ior: f32) -> Self {
+        self.ior = ior;
+        self
+    }
+
+    /// Set the emitted radiance.
+    pub fn emission(self, emission: Color) -> Self {
+        self.emission_texture(SolidColor::new(emission))
+    }
+
+    /// Set the emitted radiance from texture.
+    pub fn emission_texture<T>(mut self, tex: T) -> Self
+    where
+        T: Texture + 'static,
+    {
+        self.emission = Arc::new(tex);
+        self
+    }
+
+    /// Get the lobes seen from `wo` at the intersection, which are derived once per intersection.
+    fn lobes(&self, wo: Vec3, rec: &HitRecord) -> Arc<Lobes> {
+        rec.material_state(self, || self.eval_lobes(wo, rec))
+    }
+
+    /// Evaluate the textures at the intersection and derive the lobes seen from `wo`.
+    fn eval_lobes(&self, wo: Vec3, rec: &HitRecord) -> Lobes {
+        let sample = |tex: &Arc<dyn Texture>| tex.sample_hit(rec);
+        let scalar = |tex: &Arc<dyn Texture>| sample(tex).x.clamp(0.0, 1.0);
+
+        let base = sample(&self.base_color);
+        let metallic = scalar(&self.metallic);
+        let roughness = scalar(&self.roughness);
+        let smooth = Ggx::new(roughness, roughness).is_smooth();
+        let roughness = roughness.max(MIN_ROUGHNESS);
+        let transmission = scalar(&self.transmission);
+        let clearcoat = scalar(&self.clearcoat);
+        let clearcoat_roughness = scalar(&self.clearcoat_roughness).max(MIN_ROUGHNESS);
+
+        // The hue of the base color used by the tint parameters.
+        let tint = if luminance(base) > 0.0 {
+            base / luminance(base)
+        } else {
+            color::WHITE
+        };
+        let specular = 0.08 * scalar(&self.specular);
+        let dielectric_f0 = specular * color::WHITE.lerp(tint, scalar(&self.specular_tint));
+        let f0 = dielectric_f0.lerp(base, metallic);
+        let sheen = scalar(&self.sheen) * color::WHITE.lerp(tint, scalar(&self.sheen_tint));
+
+        let diffuse_weight = (1.0 - metallic) * (1.0 - transmission);
+        let specular_weight = 1.0 - (1.0 - metallic) * transmission;
+        let transmission_weight = (1.0 - metallic) * transmission;
+        let clearcoat_weight = 0.25 * clearcoat;
+
+        // Choose lobes in proportion to their rough reflectance seen from `wo`.
+        let mut probs = [
+            diffuse_weight * luminance(base),
+            specular_weight * luminance(fresnel_schlick(wo.z, f0)),
+            transmission_weight,
+            clearcoat_weight * fresnel_schlick(wo.z, Color::splat(CLEARCOAT_F0)).x,
+        ];
+        let total: f32 = probs.iter().sum();
+        if total > 0.0 {
+            probs.iter_mut().for_each(|p| *p /= total);
+        } else {
+            probs = [1.0, 0.0, 0.0, 0.0];
+        }
+
+        Lobes {
+            base,
+            roughness,
+            smooth,
+            f0,
+            sheen,
+            eta: if rec.front_face {
+                self.ior
+            } else {
+                1.0 / self.ior
+            },
+            distribution: Ggx::new(roughness, roughness),
+            clearcoat: Ggx::new(clearcoat_roughness, clearcoat_roughness),
+            diffuse_weight,
+            specular_weight,
+            transmission_weight,
+            clearcoat_weight,
+            probs,
+        }
+    }
+}
+
+/// The lobes of a principled material evaluated at an intersection.
+struct Lobes {
+    /// The base color.
+    base: Color,
+
+    /// The perceptual roughness.
+    roughness: f32,
+
+    /// Whether the specular and transmission lobes are a perfect mirror and refraction.
+    smooth: bool,
+
+    /// The specular reflectance at normal incidence.
+    f0: Color,
+
+    /// The sheen color.
+    sheen: Color,
+
+    /// The index of refraction of the far side over the near side.
+    eta: f32,
+
+    /// The distribution of the specular and transmission lobes.
+    distribution: Ggx,
+
+    /// The distribution of the clearcoat lobe.
+    clearcoat: Ggx,
+
+    /// The weights of each lobe.
+    diffuse_weight: f32,
+    specular_weight: f32,
+    transmission_weight: f32,
+    clearcoat_weight: f32,
+
+    /// The probability of sampling the diffuse, specular, transmission and clearcoat lobes.
+    probs: [f32; 4],
+}
+
+impl Lobes {
+    /// Sample a scattered direction in the local frame, together with its weight if it was
+    /// sampled from a perfect mirror or refraction.
+    fn sample(&self, wo: Vec3) -> (Vec3, Option<Color>) {
+        let u = random();
+        let [diffuse, specular, transmission, _] = self.probs;
+        if u < diffuse {
+            (random_cosine_weight_on_hemisphere(), None)
+        } else if u < diffuse + specular {
+            if self.smooth {
+                let wi = Vec3::new(-wo.x, -wo.y, wo.z);
+                let weight = self.specular_weight * fresnel_schlick(wo.z, self.f0) / specular;
+                return (wi, Some(weight));
+            }
+            (self.distribution.sample_reflection(wo), None)
+        } else if u < diffuse + specular + transmission {
+            let wm = if self.smooth {
+                Vec3::Z
+            } else {
+                self.distribution.sample_wm(wo)
+            };
+            let wi = if fresnel_dielectric(wo.dot(wm), self.eta) > random() {
+                (-wo).reflect(wm)
+            } else {
+                (-wo).refract(wm, 1.0 / self.eta)
+            };
+
+            // The choice by the Fresnel reflectance cancels it out of the weight.
+            let weight = self.smooth.then(|| {
+                let tint = if wi.z < 0.0 { self.base } else { color::WHITE };
+                self.transmission_weight * tint / transmission
+            });
+            (wi, weight)
+        } else {
+            (self.clearcoat.sample_reflection(wo), None)
+        }
+    }
+
+    /// Get the probability of sampling a lobe which is not a perfect mirror or refraction, by
+    /// which `eval` and `pdf` are normalized.
+    fn non_specular_probability(&self) -> f32 {
+        let [diffuse, _, _, clearcoat] = self.probs;
+        if self.smooth {
+            diffuse + clearcoat
+        } else {
+            1.0
+        }
+    }
+
+    /// Evaluate the scattering function multiplied by the cosine term in the local frame.
+    fn eval(&self, wo: Vec3, wi: Vec3) -> Color {
+        let probability = self.non_specular_probability();
+        if wo.z <= 0.0 || probability <= 0.0 {
+            return color::BLACK;
+        }
+        if wi.z < 0.0 {
+            if self.smooth {
+                return color::BLACK;
+            }
+            return self.distribution.eval_refraction(wo, wi, self.eta).map_or(
+                color::BLACK,
+                |(wm, value, _)| {
+                    let t = 1.0 - fresnel_dielectric(wo.dot(wm), self.eta);
+                    self.transmission_weight * t * value * self.base
+                },
+            );
+        }
+        if wi.z <= 0.0 {
+            return color::BLACK;
+        }
+        let wm = (wo + wi).normalize();
+        let cos_d = wi.dot(wm);
+
+        // Burley's diffuse with retro-reflection at grazing angles, and the sheen on top.
+        let fd90 = 0.5 + 2.0 * self.roughness * cos_d * cos_d;
+        let fl = schlick_weight(wi.z);
+        let fv = schlick_weight(wo.z);
+        let diffuse = self.base / PI * (1.0 + (fd90 - 1.0) * fl) * (1.0 + (fd90 - 1.0) * fv);
+        let sheen = self.sheen * schlick_weight(cos_d);
+        let mut f = self.diffuse_weight * (diffuse + sheen) * wi.z;
+
+        if !self.smooth
+            && let Some((wm, value, _)) = self.distribution.eval_reflection(wo, wi)
+        {
+            f += self.specular_weight * fresnel_schlick(wo.dot(wm), self.f0) * value;
+            f += self.transmission_weight * fresnel_dielectric(wo.dot(wm), self.eta) * value;
+        }
+        if let Some((wm, value, _)) = self.clearcoat.eval_reflection(wo, wi) {
+            let fresnel = fresnel_schlick(wo.dot(wm), Color::splat(CLEARCOAT_F0));
+            f += self.clearcoat_weight * fresnel * value;
+        }
+        f / probability
+    }
+
+    /// Get the probability density of sampling `wi` by `sample` in the local frame, given that a
+    /// lobe which is not a perfect mirror or refraction was chosen.
+    fn pdf(&self, wo: Vec3, wi: Vec3) -> f32 {
+        let probability = self.non_specular_probability();
+        if probability <= 0.0 {
+            return 0.0;
+        }
+        let [diffuse, specular, transmission, clearcoat] = self.probs;
+        if wi.z < 0.0 {
+            if self.smooth {
+                return 0.0;
+            }
+            return self.distribution.eval_refraction(wo, wi, self.eta).map_or(
+                0.0,
+                |(wm, _, pdf)| {
+                    let t = 1.0 - fresnel_dielectric(wo.dot(wm), self.eta);
+                    transmission * t * pdf
+                },
+            );
+        }
+        let mut pdf = diffuse * wi.z.max(0.0) / PI;
+        if !self.smooth
+            && let Some((wm, _, reflection)) = self.distribution.eval_reflection(wo, wi)
+        {
+            let r = fresnel_dielectric(wo.dot(wm), self.eta);
+            pdf += (specular + transmission * r) * reflection;
+        }
+        if let Some((_, _, reflection)) = self.clearcoat.eval_reflection(wo, wi) {
+            pdf += clearcoat * reflection;
+        }
+        pdf / probability
+    }
+}
+
+/// Get the Schlick weight `(1 - cos)^5` of Fresnel blending.
+fn schlick_weight(cos: f32) -> f32 {
+    (1.0 - cos.clamp(0.0, 1.0)).powi(5)
+}
+
+impl Material for Principled {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Color, Ray)> {
+        let (attenuation, scatter, _) = self.sample_scatter(r_in, rec)?;
+        Some((attenuation, scatter))
+    }
+
+    fn sample_scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Color, Ray, bool)> {
+        let frame = shading_frame(rec);
+        let wo = frame.local(-r_in.dir.normalize());
+        if wo.z <= 0.0 {
+            return None;
+        }
+        let lobes = self.lobes(wo, rec);
+        let (wi, weight) = lobes.sample(wo);
+        let scatter = Ray::new(rec.p, frame.transform(wi), r_in.t);
+        if let Some(weight) = weight {
+            return Some((weight, scatter, true));
+        }
+
+        let pdf = lobes.pdf(wo, wi);
+        let attenuation = if pdf > 0.0 {
+            lobes.eval(wo, wi) / pdf
+        } else {
+            color::BLACK
+        };
+        Some((attenuation, scatter, false))
+    }
+
+    fn scatter_pdf(&self, r_in: &Ray, r_out: &Ray, rec: &HitRecord) -> f32 {
+        let (_, wo, wi) = local_directions(r_in, r_out, rec);
+        if wo.z <= 0.0 {
+            return 0.0;
+        }
+        self.lobes(wo, rec).pdf(wo, wi)
+    }
+
+    fn eval(&self, r_in: &Ray, r_out: &Ray, rec: &HitRecord) -> Color {
+        let (_, wo, wi) = local_directions(r_in, r_out, rec);
+        if wo.z <= 0.0 {
+            return color::BLACK;
+        }
+        self.lobes(wo, rec).eval(wo, wi)
+    }
+
+    fn emit(&self, _r_in: &Ray, rec: &HitRecord) -> Color {
+        if rec.front_face {
+            self.emission.sample_hit(rec)
+        } else {
+            color::BLACK
+        }
+    }
+
+    fn power(&self, area: f32) -> f32 {
+        luminance(self.emission.sample(0.5, 0.5, Vec3::ZERO)) * PI * area
+    }
+}
@@ -0,0 +1,122 @@
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use crate::{
+    color::{self, Color},
+    material::{
+        Material,
+        microfacet::{Charlie, local_directions, shading_frame},
+    },
+    math::{Ray, Vec3, vec3::random_cosine_weight_on_hemisphere},
+    shape::HitRecord,
+    texture::{Texture, solid_color::SolidColor},
+};
+
+/// The number of cosines at which the directional albedo of the sheen is tabulated.
+const ALBEDO_RES: usize = 32;
+
+/// The number of polar and azimuthal angles used to integrate the directional albedo.
+const ALBEDO_SAMPLES: usize = 32;
+
+/// A sheen lobe scattered by fibers standing up from the surface, which gives the soft rim
+/// highlights of velvet and other cloth.
+#[derive(Clone)]
+pub struct Sheen {
+    /// The color of the sheen.
+    pub tex: Arc<dyn Texture>,
+
+    /// The distribution of fibers.
+    pub distribution: Charlie,
+
+    /// The fraction of light reflected by the sheen of unit color at each tabulated cosine.
+    albedo: [f32; ALBEDO_RES],
+}
+
+impl Sheen {
+    /// Create a sheen from color and roughness.
+    pub fn new(color: Color, roughness: f32) -> Self {
+        Self::from_texture(SolidColor::new(color), roughness)
+    }
+
+    /// Create a sheen from texture and roughness.
+    pub fn from_texture<T>(tex: T, roughness: f32) -> Self
+    where
+        T: Texture + 'static,
+    {
+        let distribution = Charlie::new(roughness);
+        Self {
+            tex: Arc::new(tex),
+            distribution,
+            albedo: directional_albedo(&distribution),
+        }
+    }
+
+    /// Get the fraction of light reflected by the sheen of unit color towards the direction with
+    /// cosine `cos`.
+    pub fn albedo(&self, cos: f32) -> f32 {
+        let x = (cos.clamp(0.0, 1.0) * ALBEDO_RES as f32 - 0.5).clamp(0.0, (ALBEDO_RES - 1) as f32);
+        let i = (x as usize).min(ALBEDO_RES - 2);
+        let t = x - i as f32;
+        self.albedo[i] + (self.albedo[i + 1] - self.albedo[i]) * t
+    }
+
+    /// Get the weight of the material below the sheen of `color` between the directions with
+    /// cosines `wo` and `wi`, which leaves out the light reflected by the sheen so that their sum
+    /// conserves energy.
+    pub fn base_weight(&self, color: Color, wo: f32, wi: f32) -> f32 {
+        let strength = color.max_element();
+        (1.0 - strength * self.albedo(wo))
+            .min(1.0 - strength * self.albedo(wi))
+            .max(0.0)
+    }
+}
+
+/// Integrate the sheen of `distribution` over the hemisphere for each tabulated cosine of the
+/// direction towards the viewer.
+fn directional_albedo(distribution: &Charlie) -> [f32; ALBEDO_RES] {
+    let n = ALBEDO_SAMPLES as f32;
+    std::array::from_fn(|i| {
+        let cos_o = (i as f32 + 0.5) / ALBEDO_RES as f32;
+        let wo = Vec3::new((1.0 - cos_o * cos_o).sqrt(), 0.0, cos_o);
+        let mut sum = 0.0;
+        for j in 0..ALBEDO_SAMPLES {
+            // Sample the cosine uniformly, which absorbs the sine of the solid angle.
+            let cos_i = (j as f32 + 0.5) / n;
+            let sin_i = (1.0 - cos_i * cos_i).sqrt();
+            for k in 0..ALBEDO_SAMPLES {
+                let phi = 2.0 * PI * (k as f32 + 0.5) / n;
+                let wi = Vec3::new(sin_i * phi.cos(), sin_i * phi.sin(), cos_i);
+                sum += distribution.eval(wo, wi);
+            }
+        }
+        (sum * 2.0 * PI / (n * n)).min(1.0)
+    })
+}
+
+impl Material for Sheen {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Color, Ray)> {
+        let frame = shading_frame(rec);
+        let scatter = Ray::new(
+            rec.p,
+            frame.transform(random_cosine_weight_on_hemisphere()),
+            r_in.t,
+        );
+        let pdf = self.scatter_pdf(r_in, &scatter, rec);
+        let attenuation = if pdf > 0.0 {
+            self.eval(r_in, &scatter, rec) / pdf
+        } else {
+            color::BLACK
+        };
+        Some((attenuation, scatter))
+    }
+
+    fn scatter_pdf(&self, _r_in: &Ray, r_out: &Ray, rec: &HitRecord) -> f32 {
+        let cos = rec.shading_normal.dot(r_out.dir.normalize());
+        cos.max(0.0) / PI
+    }
+
+    fn eval(&self, r_in: &Ray, r_out: &Ray, rec: &HitRecord) -> Color {
+        let (_, wo, wi) = local_directions(r_in, r_out, rec);
+        self.tex.sample_hit(rec) * self.distribution.eval(wo, wi)
+    }
+}
@@ -0,0 +1,165 @@
+use std::f32;
+use std::sync::Arc;
+
+use crate::{
+    color::Color,
+    interval::Interval,
+    material::{Material, microfacet::fresnel_dielectric},
+    math::{
+        Ray, random,
+        vec3::{random_cosine_weight_on_hemisphere, random_unit_vector},
+    },
+    onb::ONB,
+    shape::{Bounded, HitRecord},
+    texture::{Texture, solid_color::SolidColor},
+};
+
+/// A translucent material scattering light below the surface of a closed object, like skin,
+/// marble and wax.
+///
+/// Light entering the smooth surface reflects by the Fresnel reflectance, or refracts and walks
+/// randomly through the medium inside the shape of the object within a single scattering, limited
+/// by its own number of steps. Each flight is compared against the distance to the boundary as in
+/// `ConstantMedium`: a shorter flight scatters isotropically from the interior point as
+/// `Isotropic` does, otherwise the walk reflects back inside by the Fresnel reflectance or ends
+/// with a ray towards the boundary. There the light leaves diffusely, so that the exit point is
+/// lit by light sampling like a diffuse surface.
+#[derive(Clone)]
+pub struct Subsurface {
+    /// The color of the material after many scattering events.
+    pub tex: Arc<dyn Texture>,
+
+    /// The mean distance between scattering events for the red, green and blue channels.
+    pub mean_free_path: Color,
+
+    /// The index of refraction of the surface.
+    pub index: f32,
+
+    /// The maximal number of steps of a walk inside, after which light is absorbed.
+    pub max_steps: u32,
+}
+
+impl Subsurface {
+    /// Create a subsurface material from albedo and mean free path per color channel.
+    pub fn new(albedo: Color, mean_free_path: Color) -> Self {
+        Self::from_texture(SolidColor::new(albedo), mean_free_path)
+    }
+
+    /// Create a subsurface material from albedo texture and mean free path per color channel.
+    pub fn from_texture<T>(tex: T, mean_free_path: Color) -> Self
+    where
+        T: Texture + 'static,
+    {
+        Self {
+            tex: Arc::new(tex),
+            mean_free_path,
+            index: 1.4,
+            max_steps: 256,
+        }
+    }
+
+    /// Set the index of refraction of the surface.
+    pub const fn index(mut self, index: f32) -> Self {
+        self.index = index;
+        self
+    }
+
+    /// Set the maximal number of steps of a walk inside.
+    pub const fn max_steps(mut self, max_steps: u32) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// Get the single scattering albedo of the medium which gives the multiple scattering albedo
+    /// of the texture, from the inversion of van de Hulst's relation by Chiang et al.
+    fn single_scattering_albedo(&self, rec: &HitRecord) -> Color {
+        let albedo = self.tex.sample_hit(rec).clamp(Color::ZERO, Color::ONE);
+        let root = (9.59217 + 41.6808 * albedo + 17.7126 * albedo * albedo).powf(0.5);
+        let t = 4.09712 + 4.20863 * albedo - root;
+        Color::ONE - t * t
+    }
+
+    /// Walk randomly inside the shape from the refracted ray, and return the throughput and the
+    /// ray towards the boundary where the walk leaves, or `None` if the light is absorbed.
+    fn walk(&self, shape: &dyn Bounded, albedo: Color, mut ray: Ray) -> Option<(Color, Ray)> {
+        let extinction = Color::ONE / self.mean_free_path.max(Color::splat(1e-6));
+        let mut throughput = Color::ONE;
+        for _ in 0..self.max_steps {
+            // Leave through an open shape where there is no boundary ahead.
+            let mut rec = HitRecord::default();
+            if !shape.intersect(&ray, Interval::new(1e-3, f32::INFINITY), &mut rec) {
+                return Some((throughput, ray));
+            }
+
+            // Sample a free flight from the extinction of one channel chosen uniformly, and
+            // weight it by the average density of all channels so that colored paths stay
+            // unbiased.
+            let channel = ((random() * 3.0) as usize).min(2);
+            let flight = -(1.0 - random()).ln() / extinction[channel];
+            let distance = (rec.p - ray.ori).length();
+
+            if flight < distance {
+                let transmittance = (-extinction * flight).exp();
+                let pdf = (extinction * transmittance).element_sum() / 3.0;
+                throughput *= albedo * extinction * transmittance / pdf;
+                let origin = ray.ori + ray.dir.normalize() * flight;
+                ray = Ray::new(origin, random_unit_vector(), ray.t);
+                continue;
+            }
+
+            let transmittance = (-extinction * distance).exp();
+            throughput *= transmittance / (transmittance.element_sum() / 3.0);
+            let unit_direction = ray.dir.normalize();
+            let cos = -unit_direction.dot(rec.normal).min(1.0);
+            if fresnel_dielectric(cos, 1.0 / self.index) <= random() {
+                return Some((throughput, ray));
+            }
+            ray = Ray::new(rec.p, unit_direction.reflect(rec.normal), ray.t);
+        }
+        None
+    }
+}
+
+impl Material for Subsurface {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Color, Ray)> {
+        let (attenuation, scatter, _) = self.sample_scatter(r_in, rec)?;
+        Some((attenuation, scatter))
+    }
+
+    fn sample_scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Color, Ray, bool)> {
+        // Leave diffusely where a walk reaches the boundary.
+        if !rec.front_face {
+            let uvw = ONB::new(-rec.normal);
+            let direction = uvw.transform(random_cosine_weight_on_hemisphere());
+            return Some((Color::ONE, Ray::new(rec.p, direction, r_in.t), false));
+        }
+
+        let unit_direction = r_in.dir.normalize();
+        let cos = -unit_direction.dot(rec.shading_normal).min(1.0);
+        if fresnel_dielectric(cos, self.index) > random() {
+            let reflected = unit_direction.reflect(rec.shading_normal);
+            return Some((Color::ONE, Ray::new(rec.p, reflected, r_in.t), true));
+        }
+
+        let refracted = unit_direction.refract(rec.shading_normal, 1.0 / self.index);
+        let albedo = self.single_scattering_albedo(rec);
+        let (throughput, ray) = self.walk(
+            rec.shape.as_deref()?,
+            albedo,
+            Ray::new(rec.p, refracted, r_in.t),
+        )?;
+        Some((throughput, ray, true))
+    }
+
+    fn scatter_pdf(&self, _r_in: &Ray, r_out: &Ray, rec: &HitRecord) -> f32 {
+        if rec.front_face {
+            return 0.0;
+        }
+        let cos = -rec.normal.dot(r_out.dir.normalize());
+        cos.max(0.0) / f32::consts::PI
+    }
+
+    fn eval(&self, r_in: &Ray, r_out: &Ray, rec: &HitRecord) -> Color {
+        Color::splat(self.scatter_pdf(r_in, r_out, rec))
+    }
+}
@@ -0,0 +1,221 @@
+use std::ops::{Add, Div, Mul, Sub};
+use std::sync::{Arc, LazyLock};
+
+use crate::{
+    color::Color,
+    shape::HitRecord,
+    spectrum::{rgb_weight, sample_wavelength},
+    texture::{Texture, solid_color::SolidColor},
+};
+
+/// The number of wavelengths at which the interference is evaluated and integrated into RGB.
+const FILM_SAMPLES: usize = 16;
+
+/// The wavelengths in nanometers at which the interference is evaluated with their RGB weights,
+/// normalized so that a constant reflectance integrates to gray.
+static FILM_WAVELENGTHS: LazyLock<Vec<(f32, Color)>> = LazyLock::new(|| {
+    let samples: Vec<_> = (0..FILM_SAMPLES)
+        .map(|i| {
+            let wavelength = sample_wavelength((i as f32 + 0.5) / FILM_SAMPLES as f32);
+            (wavelength, rgb_weight(wavelength))
+        })
+        .collect();
+    let total: Color = samples.iter().map(|&(_, weight)| weight).sum();
+    samples
+        .into_iter()
+        .map(|(wavelength, weight)| (wavelength, weight / total))
+        .collect()
+});
+
+/// A thin transparent film coating a surface, whose reflections interfere with each other and
+/// shift the reflected color with the viewing angle, like soap bubbles and oil slicks.
+#[derive(Clone)]
+pub struct ThinFilm {
+    /// The thickness of the film in nanometers, read from the red channel.
+    pub thickness: Arc<dyn Texture>,
+
+    /// The index of refraction of the film.
+    pub ior: f32,
+}
+
+impl ThinFilm {
+    /// Create a film from thickness in nanometers and index of refraction.
+    pub fn new(thickness: f32, ior: f32) -> Self {
+        Self::from_texture(SolidColor::new(Color::splat(thickness)), ior)
+    }
+
+    /// Create a film from thickness texture in nanometers and index of refraction.
+    pub fn from_texture<T>(thickness: T, ior: f32) -> Self
+    where
+        T: Texture + 'static,
+    {
+        Self {
+            thickness: Arc::new(thickness),
+            ior,
+        }
+    }
+
+    /// Get the reflectance of the coated surface at the incident angle with cosine `cos`, where
+    /// `outer` is the index of refraction of the incident side and `eta + ik` is the complex
+    /// index of refraction of the substrate given for the red, green and blue channels.
+    pub fn reflectance(
+        &self,
+        cos: f32,
+        outer: f32,
+        eta: Color,
+        k: Color,
+        rec: &HitRecord,
+    ) -> Color {
+        let thickness = self.thickness.sample_hit(rec).x.max(0.0);
+        FILM_WAVELENGTHS
+            .iter()
+            .map(|&(wavelength, weight)| {
+                let substrate = Complex::new(
+                    interpolate_rgb(eta, wavelength),
+                    interpolate_rgb(k, wavelength),
+                );
+                let r = airy_reflectance(cos, outer, self.ior, substrate, thickness, wavelength);
+                r * weight
+            })
+            .sum::<Color>()
+            .clamp(Color::ZERO, Color::ONE)
+    }
+}
+
+/// Interpolate a quantity given for the red, green and blue channels at `wavelength`, taking
+/// them as samples at 650, 550 and 450 nanometers.
+fn interpolate_rgb(value: Color, wavelength: f32) -> f32 {
+    let t = ((wavelength - 450.0) / 100.0).clamp(0.0, 2.0);
+    if t < 1.0 {
+        value.z + (value.y - value.z) * t
+    } else {
+        value.y + (value.x - value.y) * (t - 1.0)
+    }
+}
+
+/// Get the reflectance of unpolarized light from the medium `n1` onto a film `n2` of
+/// `thickness` over the substrate `n3` at `wavelength`, summing the reflections inside the
+/// film by Airy's formula.
+fn airy_reflectance(
+    cos: f32,
+    n1: f32,
+    n2: f32,
+    n3: Complex,
+    thickness: f32,
+    wavelength: f32,
+) -> f32 {
+    let cos1 = Complex::real(cos.clamp(0.0, 1.0));
+    let sin1_squared = 1.0 - cos.clamp(0.0, 1.0).powi(2);
+    let n1 = Complex::real(n1);
+    let n2 = Complex::real(n2);
+
+    // Snell's law in each layer, where the cosines become complex beyond the critical angle or
+    // in absorbing substrates.
+    let cos_in = |n: Complex| (Complex::real(1.0) - n1 * n1 * sin1_squared / (n * n)).sqrt();
+    let cos2 = cos_in(n2);
+    let cos3 = cos_in(n3);
+
+    // The phase difference of a round trip inside the film.
+    let phase = n2 * cos2 * (4.0 * std::f32::consts::PI * thickness / wavelength);
+    let shift = Complex::i_exp(phase);
+
+    let airy = |r12: Complex, r23: Complex| {
+        let r = (r12 + r23 * shift) / (Complex::real(1.0) + r12 * r23 * shift);
+        r.norm_squared()
+    };
+    let s = airy(fresnel_s(n1, cos1, n2, cos2), fresnel_s(n2, cos2, n3, cos3));
+    let p = airy(fresnel_p(n1, cos1, n2, cos2), fresnel_p(n2, cos2, n3, cos3));
+    ((s + p) / 2.0).clamp(0.0, 1.0)
+}
+
+/// Get the Fresnel amplitude reflection coefficient of s-polarized light.
+fn fresnel_s(ni: Complex, cos_i: Complex, nt: Complex, cos_t: Complex) -> Complex {
+    (ni * cos_i - nt * cos_t) / (ni * cos_i + nt * cos_t)
+}
+
+/// Get the Fresnel amplitude reflection coefficient of p-polarized light.
+fn fresnel_p(ni: Complex, cos_i: Complex, nt: Complex, cos_t: Complex) -> Complex {
+    (nt * cos_i - ni * cos_t) / (nt * cos_i + ni * cos_t)
+}
+
+/// A complex number used by the wave optics of thin films.
+#[derive(Clone, Copy, Debug)]
+struct Complex {
+    re: f32,
+    im: f32,
+}
+
+impl Complex {
+    const fn new(re: f32, im: f32) -> Self {
+        Self { re, im }
+    }
+
+    const fn real(re: f32) -> Self {
+        Self { re, im: 0.0 }
+    }
+
+    fn norm_squared(self) -> f32 {
+        self.re * self.re + self.im * self.im
+    }
+
+    /// Get the principal square root.
+    fn sqrt(self) -> Self {
+        let norm = self.norm_squared().sqrt();
+        let re = ((norm + self.re) / 2.0).max(0.0).sqrt();
+        let im = ((norm - self.re) / 2.0).max(0.0).sqrt();
+        Self::new(re, if self.im < 0.0 { -im } else { im })
+    }
+
+    /// Get `exp(i * self)`.
+    fn i_exp(self) -> Self {
+        let scale = (-self.im).exp();
+        Self::new(scale * self.re.cos(), scale * self.re.sin())
+    }
+}
+
+impl Add for Complex {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.re + rhs.re, self.im + rhs.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.re - rhs.re, self.im - rhs.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self::new(
+            self.re * rhs.re - self.im * rhs.im,
+            self.re * rhs.im + self.im * rhs.re,
+        )
+    }
+}
+
+impl Mul<f32> for Complex {
+    type Output = Self;
+
+    fn mul(self, rhs: f32) -> Self {
+        Self::new(self.re * rhs, self.im * rhs)
+    }
+}
+
+impl Div for Complex {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self {
+        let norm = rhs.norm_squared();
+        Self::new(
+            (self.re * rhs.re + self.im * rhs.im) / norm,
+            (self.im * rhs.re - self.re * rhs.im) / norm,
+        )
+    }
+}
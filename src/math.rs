@@ -2,6 +2,8 @@ use glam::{Mat4, Vec3A, Vec4Swizzles};
 use rand::Rng;
 use std::f32;
 
+use crate::spectrum::WAVELENGTH_DEFAULT;
+
 pub fn random() -> f32 {
     rand::rng().random()
 }
@@ -77,6 +79,19 @@ pub mod vec3 {
         Vec3::new(x, y, z)
     }
 
+    /// Randomly generate a unit vector inside the cone around z-axis whose half angle has cosine
+    /// `cos_theta_max` using uniform probability density sampling.
+    #[inline]
+    pub fn random_in_cone(cos_theta_max: f32) -> Vec3 {
+        let r1 = random();
+        let r2 = random();
+        let z = 1.0 + r2 * (cos_theta_max - 1.0);
+        let sin_theta = (1.0 - z * z).max(0.0).sqrt();
+        let x = (2.0 * f32::consts::PI * r1).cos() * sin_theta;
+        let y = (2.0 * f32::consts::PI * r1).sin() * sin_theta;
+        Vec3::new(x, y, z)
+    }
+
     /// Randomly generate a vector in a unit disk.
     pub fn random_in_unit_disk() -> Vec3 {
         let [x, y] = UnitDisc.sample(&mut rand::rng());
@@ -96,7 +111,23 @@ impl Vec3Ext for Vec3 {
     }
 }
 
-#[derive(Default)]
+/// The rays offset by one pixel horizontally and vertically from a camera ray, whose
+/// intersections with the tangent plane of a hit estimate the footprint of the pixel there.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RayDifferentials {
+    /// The origin of the ray offset horizontally.
+    pub rx_ori: Point3,
+
+    /// The direction of the ray offset horizontally.
+    pub rx_dir: Vec3,
+
+    /// The origin of the ray offset vertically.
+    pub ry_ori: Point3,
+
+    /// The direction of the ray offset vertically.
+    pub ry_dir: Vec3,
+}
+
 /// A ray can be represented as: `A + t*B` where `A` is origin, `B` is direction, and `t` is a scalar.
 /// For any given value of t, we can compute the point along the ray using the `at` method below.
 pub struct Ray {
@@ -111,6 +142,23 @@ pub struct Ray {
     /// We use macro time `t` in `Ray` to distinguish different ray and micro time `t` in `HitRecord`
     /// to distinguish different point in the same ray.
     pub t: f32,
+
+    /// The hero wavelength in nanometers sampled for the path of the ray, which is used by
+    /// wavelength dependent materials.
+    pub wavelength: f32,
+
+    /// Whether the path has been collapsed onto its hero wavelength by a wavelength dependent
+    /// material, so that it no longer carries the other wavelengths.
+    pub monochromatic: bool,
+
+    /// The differentials of camera rays, which are `None` for scattered rays.
+    pub differentials: Option<RayDifferentials>,
+}
+
+impl Default for Ray {
+    fn default() -> Self {
+        Self::new(Point3::ZERO, Vec3::ZERO, 0.0)
+    }
 }
 
 impl Ray {
@@ -120,22 +168,46 @@ impl Ray {
             ori: origin,
             dir: direction,
             t: time,
+            wavelength: WAVELENGTH_DEFAULT,
+            monochromatic: false,
+            differentials: None,
         }
     }
 
+    /// Set the hero wavelength of the ray and whether its path is collapsed onto it.
+    pub const fn with_wavelength(mut self, wavelength: f32, monochromatic: bool) -> Self {
+        self.wavelength = wavelength;
+        self.monochromatic = monochromatic;
+        self
+    }
+
+    /// Set the differentials of the ray.
+    pub const fn with_differentials(mut self, differentials: RayDifferentials) -> Self {
+        self.differentials = Some(differentials);
+        self
+    }
+
     /// Get the point along the ray at micro time t.
     pub fn at(&self, t: f32) -> Point3 {
         self.ori + t * self.dir
     }
 
     pub fn apply_transform(&self, trans: &Mat4) -> Self {
-        let origin = trans.mul_vec4(self.ori.extend(1.0));
+        let point = |p: Point3| trans.mul_vec4(p.extend(1.0)).xyz().to_vec3a();
         // Direction no need to translate
-        let direction = trans.mul_vec4(self.dir.extend(0.0));
+        let vector = |v: Vec3| trans.mul_vec4(v.extend(0.0)).xyz().to_vec3a();
         Self {
-            ori: origin.xyz().to_vec3a(),
-            dir: direction.xyz().to_vec3a(),
+            ori: point(self.ori),
+            dir: vector(self.dir),
             t: self.t,
+            wavelength: self.wavelength,
+            monochromatic: self.monochromatic,
+            differentials: self.differentials.map(|d| RayDifferentials {
+                rx_ori: point(d.rx_ori),
+                rx_dir: vector(d.rx_dir),
+                ry_ori: point(d.ry_ori),
+                ry_dir: vector(d.ry_dir),
+            }),
         }
     }
 }
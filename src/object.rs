@@ -1,11 +1,12 @@
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 
 use crate::{
     aabb::Aabb,
     interval::Interval,
-    material::{Material, lambertian::Lambertian},
-    math::Ray,
+    material::{Material, lambertian::Lambertian, light::Light},
+    math::{Point3, Ray, Vec3, random},
     shape::{Bounded, HitRecord, Hittable},
+    texture::Texture,
 };
 
 #[derive(Clone)]
@@ -15,6 +16,10 @@ pub struct Object {
 
     /// The material of object
     pub material: Arc<dyn Material>,
+
+    /// The optional opacity of object read from the red channel, through which rays pass where
+    /// it is zero and pass stochastically where it is fractional
+    pub opacity: Option<Arc<dyn Texture>>,
 }
 
 impl Object {
@@ -26,24 +31,57 @@ impl Object {
         Self {
             shape: Arc::new(shape),
             material: Arc::new(Lambertian::default()),
+            opacity: None,
         }
     }
 
-    /// Set material for object
-    pub fn material<T>(mut self, material: T) -> Self
+    /// Set material for object, which is given the surface area of the shape
+    pub fn material<T>(mut self, mut material: T) -> Self
     where
         T: Material + 'static,
     {
+        material.set_area(self.shape.area());
         self.material = Arc::new(material);
         self
     }
+
+    /// Set light material for object whose power is converted into radiance with the surface
+    /// area of the shape
+    pub fn light(self, light: Light) -> Self {
+        self.material(light)
+    }
+
+    /// Set opacity texture for object to cut out its transparent regions, e.g. the alpha of an
+    /// image texture
+    pub fn opacity<T>(mut self, opacity: T) -> Self
+    where
+        T: Texture + 'static,
+    {
+        self.opacity = Some(Arc::new(opacity));
+        self
+    }
 }
 
 impl Hittable for Object {
     /// Get HitRecord of ray with object
     fn intersect(&self, r: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool {
         rec.material = Some(self.material.clone());
-        self.shape.intersect(r, ray_t, rec)
+        rec.shape = Some(self.shape.clone());
+        rec.material_state = OnceLock::new();
+        let Some(opacity) = &self.opacity else {
+            return self.shape.intersect(r, ray_t, rec);
+        };
+
+        // Skip hits on transparent regions and keep looking for hits behind them
+        let mut ray_t = ray_t;
+        while self.shape.intersect(r, ray_t, rec) {
+            let alpha = opacity.sample(rec.u, rec.v, rec.p).x;
+            if alpha >= 1.0 || (alpha > 0.0 && random() < alpha) {
+                return true;
+            }
+            ray_t.min = rec.t.next_up();
+        }
+        false
     }
 }
 
@@ -52,4 +90,19 @@ impl Bounded for Object {
     fn bbox(&self) -> Aabb {
         self.shape.bbox()
     }
+
+    /// Get surface area of object
+    fn area(&self) -> f32 {
+        self.shape.area()
+    }
+
+    /// Sample direction from origin towards object
+    fn sample_towards(&self, origin: Point3) -> Vec3 {
+        self.shape.sample_towards(origin)
+    }
+
+    /// Get probability density of sampling direction from origin towards object
+    fn pdf_towards(&self, origin: Point3, dir: Vec3) -> f32 {
+        self.shape.pdf_towards(origin, dir)
+    }
 }
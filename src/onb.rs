@@ -21,8 +21,26 @@ impl ONB {
         Self { u, v, w }
     }
 
+    /// Create a new `ONB` struct from given z-axis vector and a tangent which is projected onto
+    /// the plane orthogonal to it to become the x-axis. Fall back to `new` if the tangent is
+    /// degenerate.
+    pub fn from_tangent(vec: Vec3, tangent: Vec3) -> Self {
+        let w = vec.normalize();
+        let u = (tangent - w * w.dot(tangent)).normalize_or_zero();
+        if u == Vec3::ZERO {
+            return Self::new(w);
+        }
+        let v = w.cross(u);
+        Self { u, v, w }
+    }
+
     /// Transform the coordinates of vec to `ONB`'s coordinates.
     pub fn transform(&self, vec: Vec3) -> Vec3 {
         vec.x * self.u + vec.y * self.v + vec.z * self.w
     }
+
+    /// Transform the world vec into local coordinates of `ONB`, the inverse of `transform`.
+    pub fn local(&self, vec: Vec3) -> Vec3 {
+        Vec3::new(vec.dot(self.u), vec.dot(self.v), vec.dot(self.w))
+    }
 }
@@ -1,3 +1,7 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
 use image::RgbImage;
 use indicatif::ProgressBar;
 use rayon::prelude::*;
@@ -6,10 +10,35 @@ use crate::buffer::Buffer;
 use crate::camera::Camera;
 use crate::color::{self, Color};
 use crate::interval::Interval;
+use crate::material::Material;
 use crate::math::Ray;
 use crate::math::random;
 use crate::scene::Scene;
 use crate::shape::{HitRecord, Hittable};
+use crate::spectrum;
+
+/// A token which can be shared across threads to stop a running render.
+#[derive(Clone, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    /// Create a token which has not been cancelled.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Request the render holding this token to stop.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Determine whether the token has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
 
 pub struct Renderer {
     /// The camera to use
@@ -32,6 +61,15 @@ pub struct Renderer {
 
     /// The progress bar to show
     pub pb: Option<ProgressBar>,
+
+    /// The wall-clock time after which no more samples are taken.
+    pub deadline: Option<Instant>,
+
+    /// The token to abort the render from another thread.
+    pub cancel: Option<CancelToken>,
+
+    /// Whether paths carry spectral values at a few sampled wavelengths instead of RGB colors.
+    pub spectral: bool,
 }
 
 impl Renderer {
@@ -45,6 +83,9 @@ impl Renderer {
             pb: None,
             max_bounces: 50,
             num_samples: 100,
+            deadline: None,
+            cancel: None,
+            spectral: false,
         }
     }
 
@@ -78,6 +119,49 @@ impl Renderer {
         self
     }
 
+    /// Set the wall-clock deadline of the render.
+    pub const fn deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Set the time budget of the render which counts from now.
+    pub fn time_budget(mut self, budget: Duration) -> Self {
+        self.deadline = Some(Instant::now() + budget);
+        self
+    }
+
+    /// Set the token used to cancel the render.
+    pub fn cancel_token(mut self, token: CancelToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// Set whether to render spectrally. RGB colors of the scene are upsampled into spectra and
+    /// the result is converted back to RGB through CIE XYZ.
+    pub const fn spectral(mut self, spectral: bool) -> Self {
+        self.spectral = spectral;
+        self
+    }
+
+    /// Get the values of an RGB color of the scene carried by the path of the ray, which are the
+    /// spectral values at its wavelengths in spectral mode.
+    fn upsample(&self, color: Color, ray: &Ray) -> Color {
+        if self.spectral {
+            spectrum::upsample(color, spectrum::wavelengths(ray.wavelength))
+        } else {
+            color
+        }
+    }
+
+    /// Determine whether the render should stop because it was cancelled or ran out of time.
+    pub fn should_stop(&self) -> bool {
+        self.cancel.as_ref().is_some_and(CancelToken::is_cancelled)
+            || self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
     /// Trace the ray and return the color.
     pub fn trace_ray(&self, ray: &Ray, num_bounces: u32, rec: &mut HitRecord) -> Color {
         if num_bounces == 0 {
@@ -86,71 +170,165 @@ impl Renderer {
 
         // Start ray interval above zero to avoid shadow acne.
         if !self.intersect(ray, Interval::new(1e-3, f32::INFINITY), rec) {
-            return self.scene.background;
+            return self.upsample(self.scene.escape(ray.dir), ray);
+        }
+        rec.set_differentials(ray);
+
+        // The material could use `unwrap` because it will not be `None` if scene.intersect is true.
+        let material = rec.material.clone().unwrap();
+        let color_from_emission = self.upsample(material.emit(ray, rec), ray);
+
+        let Some((attenuation, scatter, specular)) = material.sample_scatter(ray, rec) else {
+            return color_from_emission;
+        };
+
+        // Collapse the path onto its hero wavelength at the first wavelength dependent material,
+        // weighted by the color of that wavelength in RGB mode, or by terminating the other
+        // wavelengths in spectral mode.
+        let collapse = material.is_dispersive() && !ray.monochromatic;
+        let weight = match (collapse, self.spectral) {
+            (false, _) => color::WHITE,
+            (true, false) => spectrum::rgb_weight(ray.wavelength),
+            (true, true) => Color::new(3.0, 0.0, 0.0),
+        };
+        let mut scatter = scatter.with_wavelength(ray.wavelength, ray.monochromatic || collapse);
+
+        if specular {
+            return color_from_emission
+                + weight
+                    * self.upsample(attenuation, ray)
+                    * self.trace_ray(&scatter, num_bounces - 1, rec);
         }
 
-        let color_from_emission = rec.material.as_ref().unwrap().emit(rec.u, rec.v, rec.p);
-
-        // The material could use `unwrap` instead of `map_or` because it will not be `None` if
-        // scene.intersect is true.
-        if let Some((attenuation, scatter)) = rec.material.as_ref().unwrap().scatter(ray, rec) {
-            let scatter_pdf = rec
-                .material
-                .as_ref()
-                .unwrap()
-                .scatter_pdf(ray, &scatter, rec);
-            let pdf_value = scatter_pdf;
-            let color_from_scatter =
-                attenuation * scatter_pdf * self.trace_ray(&scatter, num_bounces - 1, rec)
-                    / pdf_value;
-
-            color_from_emission + color_from_scatter
+        let color_from_lights = self.direct_lighting(ray, rec, material.as_ref());
+
+        // Sample the scattered direction from a mixture of the light sources and the material, so
+        // that bright and small light sources are found without relying on the material alone.
+        let lights = self.scene.light_sampler();
+        let has_lights = lights.has_emitters();
+        if has_lights && random() < 0.5 {
+            match lights.sample_emitter(rec.p) {
+                Some(dir) => scatter.dir = dir,
+                None => return color_from_emission + weight * color_from_lights,
+            }
+        }
+        let scatter_pdf = material.scatter_pdf(ray, &scatter, rec);
+        let pdf_value = if has_lights {
+            0.5 * scatter_pdf + 0.5 * lights.emitter_pdf(rec.p, scatter.dir)
         } else {
-            color_from_emission
+            scatter_pdf
+        };
+        if pdf_value <= 0.0 {
+            return color_from_emission + weight * color_from_lights;
         }
+
+        let f = self.upsample(material.eval(ray, &scatter, rec), ray);
+        let color_from_scatter = f * self.trace_ray(&scatter, num_bounces - 1, rec) / pdf_value;
+
+        color_from_emission + weight * (color_from_lights + color_from_scatter)
     }
 
-    /// Get the pixel color of a specified location in film plane.
-    pub fn get_color(&self, col: u32, row: u32, iterations: u32) -> Color {
-        let mut pixel_color = Color::default();
+    /// Estimate the light arriving directly from a light source of the scene which can not be hit
+    /// by rays, and scattered by the material towards the incident ray.
+    fn direct_lighting(&self, r_in: &Ray, rec: &HitRecord, material: &dyn Material) -> Color {
+        let Some(sample) = self.scene.light_sampler().sample_light(rec.p) else {
+            return color::BLACK;
+        };
+        let shadow = Ray::new(rec.p, sample.dir, r_in.t);
+        let shadow_t = Interval::new(1e-3, sample.distance - 1e-3);
+        if self.intersect(&shadow, shadow_t, &mut HitRecord::default()) {
+            color::BLACK
+        } else {
+            self.upsample(material.eval(r_in, &shadow, rec), r_in)
+                * self.upsample(sample.radiance, r_in)
+        }
+    }
+
+    /// Get one sample color of a specified location in film plane. The `index`-th sample of a
+    /// pixel is jittered inside the `index`-th cell of a `strata` x `strata` grid, and samples
+    /// beyond the grid are jittered over the whole pixel.
+    pub fn get_color(&self, col: u32, row: u32, index: u32, strata: u32) -> Color {
         let mut rec = HitRecord::default();
         // Sampling stratifications + Mento Carlo approximatiom.
-        let iter_sqrt = (iterations as f32).sqrt() as u32;
-        for y in 0..iter_sqrt {
-            for x in 0..iter_sqrt {
-                let s = (col as f32 + (x as f32 + random()) / iter_sqrt as f32) / self.width as f32;
-                let t =
-                    (row as f32 + (y as f32 + random()) / iter_sqrt as f32) / self.height as f32;
-                let r = self.cam.get_ray(s, t);
-                pixel_color += self.trace_ray(&r, self.max_bounces, &mut rec);
+        let (x, y, n) = if index < strata * strata {
+            (index % strata, index / strata, strata)
+        } else {
+            (0, 0, 1)
+        };
+        let s = (col as f32 + (x as f32 + random()) / n as f32) / self.width as f32;
+        let t = (row as f32 + (y as f32 + random()) / n as f32) / self.height as f32;
+
+        // Narrow the differentials as more samples are taken, since each sample then stands for
+        // a smaller part of the pixel.
+        let footprint = (self.num_samples as f32).sqrt().recip().max(0.125);
+        let r = self
+            .cam
+            .get_ray(
+                s,
+                t,
+                footprint / self.width as f32,
+                footprint / self.height as f32,
+            )
+            .with_wavelength(spectrum::sample_wavelength(random()), false);
+        let color = self.trace_ray(&r, self.max_bounces, &mut rec);
+        if self.spectral {
+            spectrum::to_rgb(color, spectrum::wavelengths(r.wavelength))
+        } else {
+            color
+        }
+    }
+
+    /// Take up to `iterations` samples of all pixels in film plane and accumulate them into
+    /// `buffer`. Samples are taken pass by pass and row by row, so that the render stops cleanly
+    /// between rows once it is cancelled or runs out of time. Return the number of finished
+    /// passes.
+    pub fn sample(&self, iterations: u32, buffer: &mut Buffer) -> u32 {
+        let strata = (iterations as f32).sqrt() as u32;
+        if let Some(pb) = self.pb.as_ref() {
+            pb.set_length(u64::from(self.height) * u64::from(iterations));
+        }
+
+        let mut passes = 0;
+        while passes < iterations && !self.should_stop() {
+            let rows: Vec<_> = (0..self.height)
+                .into_par_iter()
+                .filter_map(|row| {
+                    if self.should_stop() {
+                        return None;
+                    }
+                    let row_pixels: Vec<Color> = (0..self.width)
+                        .map(|col| self.get_color(col, row, passes, strata))
+                        .collect();
+
+                    // Update progress bar after finish each row
+                    if let Some(pb) = self.pb.as_ref() {
+                        pb.inc(1);
+                    }
+                    Some((row, row_pixels))
+                })
+                .collect();
+            let finished = rows.len() == self.height as usize;
+            for (row, row_pixels) in rows {
+                buffer.push_row(row, &row_pixels);
             }
+            if !finished {
+                break;
+            }
+            passes += 1;
         }
-        pixel_color / iterations as f32
-    }
-
-    /// Get all pixel colors in film plane and store into `buffer`.
-    pub fn sample(&self, iterations: u32, buffer: &mut Buffer) {
-        let colors: Vec<_> = (0..self.height)
-            .into_par_iter()
-            .map(|row| {
-                let row_pixels: Vec<Color> = (0..self.width)
-                    .map(|col| self.get_color(col, row, iterations))
-                    .collect();
-
-                // Update progress bar after finish each row
-                if let Some(pb) = self.pb.as_ref() {
-                    pb.inc(1);
-                }
-                row_pixels
-            })
-            .collect();
-        buffer.extend(colors);
+
         if let Some(pb) = self.pb.as_ref() {
-            pb.finish_with_message("Done!");
+            if passes < iterations {
+                pb.abandon_with_message("Stopped!");
+            } else {
+                pb.finish_with_message("Done!");
+            }
         }
+        passes
     }
 
-    /// Render the image for given scene and return `RgbImage`.
+    /// Render the image for given scene and return `RgbImage`. If the render is stopped early,
+    /// each pixel is normalized by the number of samples it actually received.
     pub fn render(&self) -> RgbImage {
         let mut buffer = Buffer::new(self.width, self.height);
         self.sample(self.num_samples, &mut buffer);
@@ -1,4 +1,10 @@
+use std::sync::{Arc, OnceLock};
+
 use crate::color::Color;
+use crate::environment::Environment;
+use crate::light::LightSource;
+use crate::light::sampler::{LightSampler, LightStrategy};
+use crate::math::Vec3;
 use crate::{bvh::BvhNode, object::Object};
 
 #[derive(Default)]
@@ -6,11 +12,24 @@ pub struct Scene {
     /// The list of objects in the scene.
     pub objects: Vec<Object>,
 
+    /// The list of light sources which are not part of the scene geometry.
+    pub(crate) lights: Vec<Arc<dyn LightSource>>,
+
     /// The BVH for the scene.
     pub bvh: Option<BvhNode>,
 
     /// The background color of the scene
     pub background: Color,
+
+    /// The environment light which replaces the background color if set.
+    pub(crate) environment: Option<Arc<dyn Environment>>,
+
+    /// The strategy to choose one light source out of many.
+    pub(crate) light_strategy: LightStrategy,
+
+    /// The sampler of light sources which is built on first use, and reset whenever the objects,
+    /// the lights, the environment or the strategy change.
+    light_sampler: OnceLock<LightSampler>,
 }
 
 impl Scene {
@@ -25,10 +44,63 @@ impl Scene {
         self
     }
 
+    /// Set the environment light of the scene.
+    pub fn environment<T>(mut self, env: T) -> Self
+    where
+        T: Environment + 'static,
+    {
+        self.environment = Some(Arc::new(env));
+        self.light_sampler = OnceLock::new();
+        self
+    }
+
+    /// Set the strategy to choose one light source out of many.
+    pub fn light_strategy(mut self, strategy: LightStrategy) -> Self {
+        self.light_strategy = strategy;
+        self.light_sampler = OnceLock::new();
+        self
+    }
+
+    /// Set the environment light of Scene.
+    pub fn set_environment<T>(&mut self, env: T) -> &mut Self
+    where
+        T: Environment + 'static,
+    {
+        self.environment = Some(Arc::new(env));
+        self.light_sampler = OnceLock::new();
+        self
+    }
+
+    /// Set the strategy of Scene to choose one light source out of many.
+    pub fn set_light_strategy(&mut self, strategy: LightStrategy) -> &mut Self {
+        self.light_strategy = strategy;
+        self.light_sampler = OnceLock::new();
+        self
+    }
+
+    /// Get the light sources which are not part of the scene geometry.
+    pub fn lights(&self) -> &[Arc<dyn LightSource>] {
+        &self.lights
+    }
+
+    /// Get the sampler of light sources, which is built from the current objects and lights on
+    /// first use.
+    pub fn light_sampler(&self) -> &LightSampler {
+        self.light_sampler.get_or_init(|| LightSampler::new(self))
+    }
+
+    /// Get the radiance of the rays escaping from the scene in direction `dir`.
+    pub fn escape(&self, dir: Vec3) -> Color {
+        self.environment
+            .as_ref()
+            .map_or(self.background, |env| env.emit(dir.normalize()))
+    }
+
     /// Builder-style add that consumes and returns the Scene.
     pub fn with(mut self, obj: Object) -> Self {
         self.objects.push(obj);
         self.bvh = None;
+        self.light_sampler = OnceLock::new();
         self
     }
 
@@ -39,6 +111,7 @@ impl Scene {
     {
         self.objects.extend(obj_list);
         self.bvh = None;
+        self.light_sampler = OnceLock::new();
         self
     }
 
@@ -46,6 +119,7 @@ impl Scene {
     pub fn add(&mut self, obj: Object) -> &mut Self {
         self.objects.push(obj);
         self.bvh = None;
+        self.light_sampler = OnceLock::new();
         self
     }
 
@@ -56,6 +130,27 @@ impl Scene {
     {
         self.objects.extend(obj_list);
         self.bvh = None;
+        self.light_sampler = OnceLock::new();
+        self
+    }
+
+    /// Builder-style add of a light source that consumes and returns the Scene.
+    pub fn with_light<T>(mut self, light: T) -> Self
+    where
+        T: LightSource + 'static,
+    {
+        self.lights.push(Arc::new(light));
+        self.light_sampler = OnceLock::new();
+        self
+    }
+
+    /// Add a light source to Scene.
+    pub fn add_light<T>(&mut self, light: T) -> &mut Self
+    where
+        T: LightSource + 'static,
+    {
+        self.lights.push(Arc::new(light));
+        self.light_sampler = OnceLock::new();
         self
     }
 
@@ -64,8 +159,10 @@ impl Scene {
         self
     }
 
-    /// Build BVH from current objects which should call after scene setup.
+    /// Build BVH from current objects which should call after scene setup. The light sampler is
+    /// rebuilt as well, since the objects may have been changed directly.
     pub fn build_bvh(&mut self) {
+        self.light_sampler = OnceLock::new();
         if self.objects.is_empty() {
             self.bvh = None;
             return;
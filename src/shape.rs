@@ -1,4 +1,7 @@
-use std::sync::Arc;
+use std::any::Any;
+use std::f32::consts::PI;
+use std::ptr;
+use std::sync::{Arc, OnceLock};
 
 use glam::{Mat3A, Mat4, Vec4, Vec4Swizzles};
 
@@ -6,7 +9,11 @@ use crate::{
     aabb::Aabb,
     interval::Interval,
     material::Material,
-    math::{Point3, Ray, Vec3},
+    math::{
+        Point3, Ray, Vec3,
+        vec3::{random_in_cone, random_unit_vector},
+    },
+    onb::ONB,
 };
 
 pub mod constant_medium;
@@ -22,6 +29,58 @@ pub trait Hittable: Send + Sync {
 pub trait Bounded: Hittable {
     /// The bounding box of the shape.
     fn bbox(&self) -> Aabb;
+
+    /// The surface area of the shape.
+    fn area(&self) -> f32;
+
+    /// Sample a direction from `origin` towards the shape, which is used to sample emissive
+    /// shapes as light sources. Default to uniform sampling of the cone subtended by the bounding
+    /// sphere of the bounding box.
+    fn sample_towards(&self, origin: Point3) -> Vec3 {
+        let (center, radius) = bounding_sphere(&self.bbox());
+        sample_sphere_cone(center, radius, origin)
+    }
+
+    /// Return the probability density in solid angle of sampling direction `dir` from `origin` by
+    /// `sample_towards`.
+    fn pdf_towards(&self, origin: Point3, dir: Vec3) -> f32 {
+        let (center, radius) = bounding_sphere(&self.bbox());
+        sphere_cone_pdf(center, radius, origin, dir)
+    }
+}
+
+/// Get the center and radius of the sphere which bounds the box.
+pub fn bounding_sphere(aabb: &Aabb) -> (Point3, f32) {
+    let min = Point3::new(aabb.x.min, aabb.y.min, aabb.z.min);
+    let max = Point3::new(aabb.x.max, aabb.y.max, aabb.z.max);
+    ((min + max) / 2.0, (max - min).length() / 2.0)
+}
+
+/// Sample a direction from `origin` uniformly inside the cone subtended by a sphere, or over the
+/// whole unit sphere if `origin` is inside the sphere.
+pub fn sample_sphere_cone(center: Point3, radius: f32, origin: Point3) -> Vec3 {
+    let to_center = center - origin;
+    let distance_squared = to_center.length_squared();
+    if distance_squared <= radius * radius {
+        return random_unit_vector();
+    }
+    let cos_theta_max = (1.0 - radius * radius / distance_squared).sqrt();
+    ONB::new(to_center).transform(random_in_cone(cos_theta_max))
+}
+
+/// Return the probability density in solid angle of sampling direction `dir` from `origin` by
+/// `sample_sphere_cone`.
+pub fn sphere_cone_pdf(center: Point3, radius: f32, origin: Point3, dir: Vec3) -> f32 {
+    let to_center = center - origin;
+    let distance_squared = to_center.length_squared();
+    if distance_squared <= radius * radius {
+        return 1.0 / (4.0 * PI);
+    }
+    let cos_theta_max = (1.0 - radius * radius / distance_squared).sqrt();
+    if dir.normalize().dot(to_center.normalize()) < cos_theta_max {
+        return 0.0;
+    }
+    1.0 / (2.0 * PI * (1.0 - cos_theta_max))
 }
 
 #[derive(Default, Clone)]
@@ -29,6 +88,13 @@ pub struct HitRecord {
     /// The 3d coordinations of intersection point.
     pub p: Point3,
 
+    /// The intersection point in the space of the shape before any `Transformed`, which stays
+    /// fixed on the surface when the object moves.
+    pub p_object: Point3,
+
+    /// The normal vector in the space of the shape before any `Transformed`, like `p_object`.
+    pub normal_object: Vec3,
+
     /// Time which can be used to compute point along the ray through the formula
     /// p = origin + t * direction. This attribute is more microscopic than the time of
     /// the `Ray` structure.
@@ -45,13 +111,58 @@ pub struct HitRecord {
     /// The material of intersect object.
     pub material: Option<Arc<dyn Material>>,
 
+    /// The shape of intersect object, which materials scattering light below the surface walk
+    /// through from inside.
+    pub shape: Option<Arc<dyn Bounded>>,
+
+    /// The state derived once by the material of intersect object, e.g. its textures evaluated
+    /// at the intersection, together with the address of the material.
+    pub material_state: OnceLock<(usize, Arc<dyn Any + Send + Sync>)>,
+
     /// The coordinates of the object surface mapping to the texture map
     pub u: f32,
     pub v: f32,
+
+    /// The partial derivatives of the intersection point with respect to `u` and `v`, which span
+    /// the tangent plane.
+    pub dpdu: Vec3,
+    pub dpdv: Vec3,
+
+    /// The normal vector used for shading on the same side as `normal`, which could be perturbed
+    /// by normal or bump maps.
+    pub shading_normal: Vec3,
+
+    /// The partial derivatives of `u` and `v` with respect to the pixel coordinates of the film,
+    /// which estimate the footprint of the pixel in the texture.
+    pub dudx: f32,
+    pub dvdx: f32,
+    pub dudy: f32,
+    pub dvdy: f32,
 }
 
 impl HitRecord {
-    /// Set the normal vector of intersections surface which face to the incident ray.
+    /// Get the state derived by `material` at the intersection, which is computed by `f` on the
+    /// first call and shared by the later calls. A material nested in another one which already
+    /// keeps its state computes it on every call.
+    pub fn material_state<M, T, F>(&self, material: &M, f: F) -> Arc<T>
+    where
+        T: Any + Send + Sync,
+        F: FnOnce() -> T,
+    {
+        let key = ptr::from_ref(material).cast::<()>().addr();
+        if let Some((owner, state)) = self.material_state.get() {
+            return match state.clone().downcast::<T>() {
+                Ok(state) if *owner == key => state,
+                _ => Arc::new(f()),
+            };
+        }
+        let state = Arc::new(f());
+        let _ = self.material_state.set((key, state.clone()));
+        state
+    }
+
+    /// Set the normal vector of intersections surface which face to the incident ray, which is
+    /// also used for shading.
     pub fn set_face_normal(&mut self, r: &Ray, outward_normal: Vec3) {
         self.front_face = r.dir.dot(outward_normal) < 0.0;
         self.normal = if self.front_face {
@@ -59,6 +170,43 @@ impl HitRecord {
         } else {
             -outward_normal
         };
+        self.shading_normal = self.normal;
+    }
+
+    /// Set the partial derivatives of the texture coordinates with respect to the pixel
+    /// coordinates, from where the differentials of the ray hit the tangent plane. They are zero
+    /// if the ray has no differentials.
+    pub fn set_differentials(&mut self, r: &Ray) {
+        (self.dudx, self.dvdx, self.dudy, self.dvdy) = (0.0, 0.0, 0.0, 0.0);
+        let Some(differentials) = r.differentials else {
+            return;
+        };
+
+        // Find the offsets of the intersection point along the tangent plane.
+        let offset = |ori: Point3, dir: Vec3| {
+            let t = (self.p - ori).dot(self.normal) / dir.dot(self.normal);
+            ori + t * dir - self.p
+        };
+        let dpdx = offset(differentials.rx_ori, differentials.rx_dir);
+        let dpdy = offset(differentials.ry_ori, differentials.ry_dir);
+        if !dpdx.is_finite() || !dpdy.is_finite() {
+            return;
+        }
+
+        // Solve `dpdu * du + dpdv * dv = dp` for `du` and `dv` in the least squares sense.
+        let a00 = self.dpdu.dot(self.dpdu);
+        let a01 = self.dpdu.dot(self.dpdv);
+        let a11 = self.dpdv.dot(self.dpdv);
+        let det = a00 * a11 - a01 * a01;
+        if det.abs() < 1e-12 {
+            return;
+        }
+        let solve = |dp: Vec3| {
+            let (b0, b1) = (self.dpdu.dot(dp), self.dpdv.dot(dp));
+            ((a11 * b0 - a01 * b1) / det, (a00 * b1 - a01 * b0) / det)
+        };
+        (self.dudx, self.dvdx) = solve(dpdx);
+        (self.dudy, self.dvdy) = solve(dpdy);
     }
 }
 
@@ -70,7 +218,6 @@ pub struct Transformed<T> {
     /// The transformation matrix to transform object.
     transform: Mat4,
 
-    #[allow(dead_code)]
     /// The transformation which extract from `transform` and not contains translate part.
     linear: Mat3A,
 
@@ -109,9 +256,22 @@ impl<T: Hittable> Hittable for Transformed<T> {
 
         // Fix normal vector by multiplying by M^-T
         rec.normal = self.normal_transform.mul_vec3a(rec.normal).normalize();
+        let shading_normal = self
+            .normal_transform
+            .mul_vec3a(rec.shading_normal)
+            .normalize();
+
+        // Tangents are transformed like directions
+        rec.dpdu = self.linear.mul_vec3a(rec.dpdu);
+        rec.dpdv = self.linear.mul_vec3a(rec.dpdv);
 
         // Check face normal against the original ray (not transformed ray)
         rec.set_face_normal(r, rec.normal);
+        rec.shading_normal = if shading_normal.dot(rec.normal) < 0.0 {
+            -shading_normal
+        } else {
+            shading_normal
+        };
         true
     }
 }
@@ -171,6 +331,12 @@ impl<T: Bounded> Bounded for Transformed<T> {
             z: Interval::new(min_z, max_z),
         }
     }
+
+    /// The surface area after transformation, which is exact for rotations, translations and
+    /// uniform scaling, and approximated by the volume scaling factor otherwise.
+    fn area(&self) -> f32 {
+        self.shape.area() * self.linear.determinant().abs().powf(2.0 / 3.0)
+    }
 }
 
 pub trait Transformable<T> {
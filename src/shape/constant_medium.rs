@@ -67,7 +67,10 @@ impl Hittable for ConstantMedium {
 
         rec.t = rec1.t + hit_distance / ray_length;
         rec.p = r.at(rec.t);
+        rec.p_object = rec.p;
         rec.normal = Vec3::new(1.0, 0.0, 0.0);
+        rec.shading_normal = rec.normal;
+        rec.normal_object = rec.normal;
         rec.front_face = true;
 
         true
@@ -78,4 +81,8 @@ impl Bounded for ConstantMedium {
     fn bbox(&self) -> Aabb {
         self.boundary.bbox()
     }
+
+    fn area(&self) -> f32 {
+        self.boundary.area()
+    }
 }
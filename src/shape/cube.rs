@@ -57,24 +57,38 @@ impl Hittable for Cube {
 
         rec.t = t;
         rec.p = r.at(t);
+        rec.p_object = rec.p;
 
-        // Calculate normal vector based on which face was hit
+        // Find the axis and the side of the face which was hit
         let epsilon = 1e-4;
-        let normal = if (rec.p.x - self.p_min.x).abs() < epsilon {
-            Vec3::new(-1.0, 0.0, 0.0)
-        } else if (rec.p.x - self.p_max.x).abs() < epsilon {
-            Vec3::new(1.0, 0.0, 0.0)
-        } else if (rec.p.y - self.p_min.y).abs() < epsilon {
-            Vec3::new(0.0, -1.0, 0.0)
-        } else if (rec.p.y - self.p_max.y).abs() < epsilon {
-            Vec3::new(0.0, 1.0, 0.0)
-        } else if (rec.p.z - self.p_min.z).abs() < epsilon {
-            Vec3::new(0.0, 0.0, -1.0)
-        } else {
-            Vec3::new(0.0, 0.0, 1.0)
-        };
+        let (axis, sign) = (0..3)
+            .find_map(|axis| {
+                if (rec.p[axis] - self.p_min[axis]).abs() < epsilon {
+                    Some((axis, -1.0))
+                } else if (rec.p[axis] - self.p_max[axis]).abs() < epsilon {
+                    Some((axis, 1.0))
+                } else {
+                    None
+                }
+            })
+            .unwrap_or((2, 1.0));
+        let mut normal = Vec3::ZERO;
+        normal[axis] = sign;
+
+        // Map the face onto the unit square along the two other axes, mirroring `u` on the faces
+        // at the minimum so that `dpdu`, `dpdv` and the outward normal are right-handed
+        let (a, b) = ((axis + 1) % 3, (axis + 2) % 3);
+        let size = self.p_max - self.p_min;
+        let u = (rec.p[a] - self.p_min[a]) / size[a];
+        rec.u = if sign > 0.0 { u } else { 1.0 - u };
+        rec.v = (rec.p[b] - self.p_min[b]) / size[b];
+        rec.dpdu = Vec3::ZERO;
+        rec.dpdv = Vec3::ZERO;
+        rec.dpdu[a] = sign * size[a];
+        rec.dpdv[b] = size[b];
 
         rec.set_face_normal(r, normal);
+        rec.normal_object = rec.normal;
         true
     }
 }
@@ -83,4 +97,9 @@ impl Bounded for Cube {
     fn bbox(&self) -> Aabb {
         self.aabb
     }
+
+    fn area(&self) -> f32 {
+        let d = (self.p_max - self.p_min).abs();
+        2.0 * (d.x * d.y + d.y * d.z + d.z * d.x)
+    }
 }
@@ -3,7 +3,7 @@ use std::f32;
 use crate::{
     aabb::Aabb,
     interval::Interval,
-    math::{Point3, Ray, Vec3},
+    math::{Point3, Ray, Vec3, random},
     shape::{Bounded, HitRecord, Hittable},
 };
 
@@ -91,7 +91,11 @@ impl Hittable for Quad {
         // Set intersection record
         rec.t = root;
         rec.p = r.at(root);
+        rec.p_object = rec.p;
         rec.set_face_normal(r, self.normal);
+        rec.normal_object = rec.normal;
+        rec.dpdu = self.u;
+        rec.dpdv = self.v;
 
         true
     }
@@ -101,4 +105,24 @@ impl Bounded for Quad {
     fn bbox(&self) -> Aabb {
         self.aabb
     }
+
+    fn area(&self) -> f32 {
+        self.u.cross(self.v).length()
+    }
+
+    fn sample_towards(&self, origin: Point3) -> Vec3 {
+        self.origin + random() * self.u + random() * self.v - origin
+    }
+
+    fn pdf_towards(&self, origin: Point3, dir: Vec3) -> f32 {
+        let mut rec = HitRecord::default();
+        let ray = Ray::new(origin, dir, 0.0);
+        if !self.intersect(&ray, Interval::new(1e-3, f32::INFINITY), &mut rec) {
+            return 0.0;
+        }
+        // Convert the uniform density over the area into solid angle.
+        let distance_squared = rec.t * rec.t * dir.length_squared();
+        let cos = (dir.dot(self.normal) / dir.length()).abs();
+        distance_squared / (cos * self.area())
+    }
 }
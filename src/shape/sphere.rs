@@ -6,7 +6,7 @@ use crate::math::Point3;
 use crate::math::Ray;
 use crate::math::Vec3;
 use crate::shape::Hittable;
-use crate::shape::{Bounded, HitRecord};
+use crate::shape::{Bounded, HitRecord, bounding_sphere, sample_sphere_cone, sphere_cone_pdf};
 
 pub struct Sphere {
     /// The center point of the sphere.
@@ -57,6 +57,18 @@ impl Sphere {
         let v = theta / PI;
         (u, v)
     }
+
+    /// Get the partial derivatives of a point `p` relative to the center with respect to the
+    /// coordinates of `get_sphere_uv`.
+    pub fn get_sphere_tangents(p: Vec3) -> (Vec3, Vec3) {
+        let radius = p.length();
+        let n = p / radius;
+        let sin_theta = (1.0 - n.y * n.y).max(1e-6).sqrt();
+        let dpdu = 2.0 * PI * Vec3::new(p.z, 0.0, -p.x);
+        let dpdv =
+            PI * radius * Vec3::new(-n.x * n.y / sin_theta, sin_theta, -n.y * n.z / sin_theta);
+        (dpdu, dpdv)
+    }
 }
 
 impl Hittable for Sphere {
@@ -82,10 +94,13 @@ impl Hittable for Sphere {
 
         rec.t = root;
         rec.p = r.at(root);
+        rec.p_object = rec.p;
         // If radius is negative, the normal is inverted. Application: hollow glass sphere.
         let normal = (rec.p - current_center) / self.radius;
         rec.set_face_normal(r, normal);
+        rec.normal_object = rec.normal;
         (rec.u, rec.v) = Self::get_sphere_uv(normal);
+        (rec.dpdu, rec.dpdv) = Self::get_sphere_tangents(rec.p - current_center);
 
         true
     }
@@ -95,4 +110,25 @@ impl Bounded for Sphere {
     fn bbox(&self) -> Aabb {
         self.aabb
     }
+
+    fn area(&self) -> f32 {
+        4.0 * PI * self.radius * self.radius
+    }
+
+    fn sample_towards(&self, origin: Point3) -> Vec3 {
+        // A moving sphere is sampled through its bounding box which covers the whole motion.
+        if self.center.dir != Vec3::ZERO {
+            let (center, radius) = bounding_sphere(&self.aabb);
+            return sample_sphere_cone(center, radius, origin);
+        }
+        sample_sphere_cone(self.center.ori, self.radius.abs(), origin)
+    }
+
+    fn pdf_towards(&self, origin: Point3, dir: Vec3) -> f32 {
+        if self.center.dir != Vec3::ZERO {
+            let (center, radius) = bounding_sphere(&self.aabb);
+            return sphere_cone_pdf(center, radius, origin, dir);
+        }
+        sphere_cone_pdf(self.center.ori, self.radius.abs(), origin, dir)
+    }
 }
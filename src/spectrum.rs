@@ -0,0 +1,134 @@
+use std::sync::LazyLock;
+
+use crate::{color::Color, math::Vec3};
+
+/// The spectral model shared with the build script, which only depends on glam and palette.
+mod model;
+
+use model::{SigmoidPolynomial, TABLE_RES, table_index, table_scale};
+pub use model::{WAVELENGTH_MAX, WAVELENGTH_MIN, cie_xyz, sample_wavelength, xyz_to_rgb};
+
+/// The wavelength in nanometers used by rays which have not sampled one.
+pub const WAVELENGTH_DEFAULT: f32 = 550.0;
+
+/// Get the wavelengths carried by a path of `hero` wavelength, which are the hero and two more
+/// rotated equally over the visible range.
+pub fn wavelengths(hero: f32) -> [f32; 3] {
+    let range = WAVELENGTH_MAX - WAVELENGTH_MIN;
+    let rotate = |k: f32| WAVELENGTH_MIN + (hero - WAVELENGTH_MIN + k * range / 3.0) % range;
+    [hero, rotate(1.0), rotate(2.0)]
+}
+
+/// The linear sRGB color of an equal energy spectrum averaged over the visible range.
+static RGB_WHITE: LazyLock<Color> = LazyLock::new(|| {
+    let n = 1000;
+    (0..n)
+        .map(|i| xyz_to_rgb(cie_xyz(sample_wavelength((i as f32 + 0.5) / n as f32))))
+        .sum::<Color>()
+        / n as f32
+});
+
+/// Get the weight of a path which carries a single uniformly sampled `wavelength`, so that
+/// averaging over wavelengths reproduces white in linear sRGB.
+pub fn rgb_weight(wavelength: f32) -> Color {
+    xyz_to_rgb(cie_xyz(wavelength)) / *RGB_WHITE
+}
+
+/// Convert the spectral values of a path at its `wavelengths` into linear sRGB through CIE XYZ,
+/// where a constant spectrum of one becomes white.
+pub fn to_rgb(values: Color, wavelengths: [f32; 3]) -> Color {
+    let xyz = (values.x * cie_xyz(wavelengths[0])
+        + values.y * cie_xyz(wavelengths[1])
+        + values.z * cie_xyz(wavelengths[2]))
+        / 3.0;
+    xyz_to_rgb(xyz) / *RGB_WHITE
+}
+
+/// A table of sigmoid polynomials fitted to a grid of RGB colors by the build script, which
+/// upsamples reflectances in [0, 1] into smooth spectra.
+///
+/// The grid is indexed by the largest channel of the color, its value and the other two channels
+/// divided by it.
+struct RgbToSpectrumTable {
+    /// The values of the largest channel at each grid point, denser near black and white.
+    scale: Vec<f32>,
+
+    /// The coefficients of each largest channel, value, and the two ratios.
+    coeffs: Vec<Vec3>,
+}
+
+/// The RGB to spectrum table, which is read on first use.
+static TABLE: LazyLock<RgbToSpectrumTable> = LazyLock::new(RgbToSpectrumTable::load);
+
+impl RgbToSpectrumTable {
+    /// Read the table fitted by the build script.
+    fn load() -> Self {
+        let bytes = include_bytes!(concat!(env!("OUT_DIR"), "/rgb_to_spectrum.bin"));
+        let floats: Vec<f32> = bytes
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        Self {
+            scale: (0..TABLE_RES).map(table_scale).collect(),
+            coeffs: floats.chunks_exact(3).map(Vec3::from_slice).collect(),
+        }
+    }
+
+    /// Find the sigmoid polynomial of a color in [0, 1] by interpolating the grid.
+    fn lookup(&self, rgb: Color) -> SigmoidPolynomial {
+        let channel = if rgb.x >= rgb.y && rgb.x >= rgb.z {
+            0
+        } else if rgb.y >= rgb.z {
+            1
+        } else {
+            2
+        };
+        let z = rgb[channel];
+        let x = rgb[(channel + 1) % 3] / z;
+        let y = rgb[(channel + 2) % 3] / z;
+
+        // Locate the cell of the grid and the interpolation parameters inside it.
+        let cell = |t: f32| {
+            let t = t * (TABLE_RES - 1) as f32;
+            let i = (t as usize).min(TABLE_RES - 2);
+            (i, t - i as f32)
+        };
+        let (xi, xt) = cell(x);
+        let (yi, yt) = cell(y);
+        let zi = self
+            .scale
+            .partition_point(|&s| s <= z)
+            .saturating_sub(1)
+            .min(TABLE_RES - 2);
+        let zt = ((z - self.scale[zi]) / (self.scale[zi + 1] - self.scale[zi])).clamp(0.0, 1.0);
+
+        let mut c = Vec3::ZERO;
+        for (dz, wz) in [(0, 1.0 - zt), (1, zt)] {
+            for (dy, wy) in [(0, 1.0 - yt), (1, yt)] {
+                for (dx, wx) in [(0, 1.0 - xt), (1, xt)] {
+                    let coeffs = self.coeffs[table_index(channel, zi + dz, yi + dy, xi + dx)];
+                    c += wz * wy * wx * coeffs;
+                }
+            }
+        }
+        SigmoidPolynomial { c }
+    }
+}
+
+/// Upsample a linear sRGB color into a smooth spectrum and get its values at `wavelengths`.
+/// Colors brighter than one, such as emitters, keep their hue and are scaled by their largest
+/// channel.
+pub fn upsample(rgb: Color, wavelengths: [f32; 3]) -> Color {
+    let rgb = rgb.max(Color::ZERO);
+    let max = rgb.max_element();
+    if max <= 0.0 {
+        return Color::ZERO;
+    }
+    let (rgb, scale) = if max > 1.0 {
+        (rgb / max, max)
+    } else {
+        (rgb, 1.0)
+    };
+    let spectrum = TABLE.lookup(rgb);
+    scale * Color::from_array(wavelengths.map(|wavelength| spectrum.eval(wavelength)))
+}
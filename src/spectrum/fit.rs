@@ -0,0 +1,110 @@
+use glam::{Mat3A, Vec3A as Color, Vec3A as Vec3};
+
+use crate::model::{
+    SigmoidPolynomial, TABLE_RES, cie_xyz, sample_wavelength, table_index, table_scale, xyz_to_rgb,
+};
+
+/// The number of wavelengths used to integrate spectra when fitting the table.
+const FIT_SAMPLES: usize = 64;
+
+/// Fit the coefficients of the table by least squares on every grid point.
+pub fn fit() -> Vec<Vec3> {
+    // The weight of each wavelength to integrate a spectrum into linear sRGB.
+    let weights: Vec<(f32, Color)> = (0..FIT_SAMPLES)
+        .map(|i| {
+            let wavelength = sample_wavelength((i as f32 + 0.5) / FIT_SAMPLES as f32);
+            (wavelength, xyz_to_rgb(cie_xyz(wavelength)))
+        })
+        .collect();
+    let white = weights.iter().map(|&(_, w)| w).sum::<Color>();
+    let rgb = |c: Vec3| {
+        let spectrum = SigmoidPolynomial { c };
+        weights
+            .iter()
+            .map(|&(wavelength, w)| spectrum.eval(wavelength) * w)
+            .sum::<Color>()
+            / white
+    };
+
+    // Walk from gray towards saturated colors, and from a moderate value towards black and
+    // white, starting each point from the solution of its neighbor.
+    let mut coeffs = vec![Vec3::ZERO; 3 * TABLE_RES.pow(3)];
+    let start = TABLE_RES / 5;
+    let last = TABLE_RES - 1;
+    for channel in 0..3 {
+        for y in (0..TABLE_RES).rev() {
+            for x in (0..TABLE_RES).rev() {
+                let ratio = |i: usize| i as f32 / (TABLE_RES - 1) as f32;
+                let target = |z: usize| {
+                    let mut target = Color::ZERO;
+                    target[channel] = table_scale(z);
+                    target[(channel + 1) % 3] = ratio(x) * table_scale(z);
+                    target[(channel + 2) % 3] = ratio(y) * table_scale(z);
+                    target
+                };
+
+                let mut c = if x < last {
+                    coeffs[table_index(channel, start, y, x + 1)]
+                } else if y < last {
+                    coeffs[table_index(channel, start, y + 1, x)]
+                } else {
+                    Vec3::ZERO
+                };
+                for z in start..TABLE_RES {
+                    c = fit_point(&rgb, target(z), c);
+                    coeffs[table_index(channel, z, y, x)] = c;
+                }
+                c = coeffs[table_index(channel, start, y, x)];
+                for z in (0..start).rev() {
+                    c = fit_point(&rgb, target(z), c);
+                    coeffs[table_index(channel, z, y, x)] = c;
+                }
+            }
+        }
+    }
+    coeffs
+}
+
+/// Refine the coefficients `c` by Levenberg-Marquardt iterations so that the spectrum integrates
+/// to the `target` color, or as close as possible if it is out of reach.
+fn fit_point<F>(rgb: &F, target: Color, mut c: Vec3) -> Vec3
+where
+    F: Fn(Vec3) -> Color,
+{
+    const EPSILON: f32 = 1e-3;
+    let mut residual = rgb(c) - target;
+    let mut damping = 1e-3;
+    for _ in 0..50 {
+        if residual.length_squared() < 1e-10 {
+            break;
+        }
+        let jacobian = Mat3A::from_cols(
+            (rgb(c + Vec3::X * EPSILON) - rgb(c - Vec3::X * EPSILON)) / (2.0 * EPSILON),
+            (rgb(c + Vec3::Y * EPSILON) - rgb(c - Vec3::Y * EPSILON)) / (2.0 * EPSILON),
+            (rgb(c + Vec3::Z * EPSILON) - rgb(c - Vec3::Z * EPSILON)) / (2.0 * EPSILON),
+        );
+        let normal = jacobian.transpose() * jacobian;
+        let gradient = jacobian.transpose() * residual;
+
+        // Increase the damping until the step reduces the error.
+        let mut improved = false;
+        while damping < 1e6 {
+            let step =
+                (normal + Mat3A::from_diagonal(Vec3::splat(damping).into())).inverse() * gradient;
+            let next = c - step;
+            let next_residual = rgb(next) - target;
+            if next.is_finite() && next_residual.length_squared() < residual.length_squared() {
+                c = next;
+                residual = next_residual;
+                damping = (damping / 10.0).max(1e-7);
+                improved = true;
+                break;
+            }
+            damping *= 10.0;
+        }
+        if !improved {
+            break;
+        }
+    }
+    c
+}
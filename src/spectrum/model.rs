@@ -0,0 +1,78 @@
+use glam::{Vec3A as Color, Vec3A as Vec3};
+use palette::{LinSrgb, Xyz, convert::FromColorUnclamped};
+
+/// The shortest wavelength in nanometers sampled by the renderer.
+pub const WAVELENGTH_MIN: f32 = 380.0;
+
+/// The longest wavelength in nanometers sampled by the renderer.
+pub const WAVELENGTH_MAX: f32 = 720.0;
+
+/// The number of grid points along each axis of the RGB to spectrum table.
+pub const TABLE_RES: usize = 24;
+
+/// Sample a wavelength uniformly in the visible range from an uniform random number `u`.
+pub fn sample_wavelength(u: f32) -> f32 {
+    WAVELENGTH_MIN + u * (WAVELENGTH_MAX - WAVELENGTH_MIN)
+}
+
+/// A piecewise Gaussian with different widths on both sides of the mean.
+fn gaussian(x: f32, mean: f32, sigma_left: f32, sigma_right: f32) -> f32 {
+    let t = (x - mean) / if x < mean { sigma_left } else { sigma_right };
+    (-0.5 * t * t).exp()
+}
+
+/// Get the CIE 1931 color matching functions at `wavelength` in nanometers from the multi-lobe
+/// fit of Wyman et al.
+pub fn cie_xyz(wavelength: f32) -> Vec3 {
+    let l = wavelength;
+    Vec3::new(
+        1.056 * gaussian(l, 599.8, 37.9, 31.0) + 0.362 * gaussian(l, 442.0, 16.0, 26.7)
+            - 0.065 * gaussian(l, 501.1, 20.4, 26.2),
+        0.821 * gaussian(l, 568.8, 46.9, 40.5) + 0.286 * gaussian(l, 530.9, 16.3, 31.1),
+        1.217 * gaussian(l, 437.0, 11.8, 36.0) + 0.681 * gaussian(l, 459.0, 26.0, 13.8),
+    )
+}
+
+/// Convert CIE XYZ tristimulus values under the D65 white point into a linear sRGB color without
+/// clamping.
+pub fn xyz_to_rgb(xyz: Vec3) -> Color {
+    let rgb = LinSrgb::from_color_unclamped(Xyz::new(xyz.x, xyz.y, xyz.z));
+    Color::new(rgb.red, rgb.green, rgb.blue)
+}
+
+/// Get the value of the largest channel at the grid point `i` of the table, which is denser
+/// near black and white.
+pub fn table_scale(i: usize) -> f32 {
+    let smoothstep = |x: f32| x * x * (3.0 - 2.0 * x);
+    smoothstep(smoothstep(i as f32 / (TABLE_RES - 1) as f32))
+}
+
+/// Get the index of the coefficients of the largest `channel`, the value `z` and the two ratios
+/// `y` and `x` in the table.
+pub const fn table_index(channel: usize, z: usize, y: usize, x: usize) -> usize {
+    ((channel * TABLE_RES + z) * TABLE_RES + y) * TABLE_RES + x
+}
+
+/// The sigmoid which maps a polynomial into a bounded spectrum in [0, 1].
+fn sigmoid(x: f32) -> f32 {
+    if x.is_infinite() {
+        return if x > 0.0 { 1.0 } else { 0.0 };
+    }
+    0.5 + x / (2.0 * (1.0 + x * x).sqrt())
+}
+
+/// A smooth reflectance spectrum given by a sigmoid of a quadratic polynomial in wavelength, as
+/// proposed by Jakob and Hanika.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SigmoidPolynomial {
+    /// The coefficients of the polynomial in the wavelength normalized to [0, 1].
+    pub c: Vec3,
+}
+
+impl SigmoidPolynomial {
+    /// Get the value of the spectrum at `wavelength` in nanometers.
+    pub fn eval(&self, wavelength: f32) -> f32 {
+        let t = (wavelength - WAVELENGTH_MIN) / (WAVELENGTH_MAX - WAVELENGTH_MIN);
+        sigmoid((self.c.x * t + self.c.y) * t + self.c.z)
+    }
+}
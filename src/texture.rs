@@ -1,11 +1,23 @@
 pub mod checker_texture;
+pub mod color_ramp;
 pub mod image_texture;
+pub mod node;
+pub mod noise;
+pub mod noise_texture;
+pub mod projection;
 pub mod solid_color;
 
 use crate::color::Color;
 use crate::math::Vec3;
+use crate::shape::HitRecord;
 
 pub trait Texture: Send + Sync {
     /// Get the color of the texture in specified location from plane coordinates.
     fn sample(&self, u: f32, v: f32, p: Vec3) -> Color;
+
+    /// Get the color of the texture at an intersection, which could be filtered over the
+    /// footprint of the pixel given by the derivatives of the texture coordinates.
+    fn sample_hit(&self, rec: &HitRecord) -> Color {
+        self.sample(rec.u, rec.v, rec.p)
+    }
 }
@@ -3,6 +3,7 @@ use std::sync::Arc;
 use crate::{
     color::Color,
     math::Point3,
+    shape::HitRecord,
     texture::{Texture, solid_color::SolidColor},
 };
 
@@ -34,14 +35,25 @@ impl CheckerTexture {
     }
 }
 
-impl Texture for CheckerTexture {
-    fn sample(&self, u: f32, v: f32, p: Point3) -> Color {
+impl CheckerTexture {
+    /// Get the texture of the tile at texture coordinates `u` and `v`.
+    fn tile(&self, u: f32, v: f32) -> &dyn Texture {
         let iu = (self.inv_scale * u).floor() as i32;
         let iv = (self.inv_scale * v).floor() as i32;
         if (iu + iv) & 1 == 0 {
-            self.even.sample(u, v, p)
+            self.even.as_ref()
         } else {
-            self.odd.sample(u, v, p)
+            self.odd.as_ref()
         }
     }
 }
+
+impl Texture for CheckerTexture {
+    fn sample(&self, u: f32, v: f32, p: Point3) -> Color {
+        self.tile(u, v).sample(u, v, p)
+    }
+
+    fn sample_hit(&self, rec: &HitRecord) -> Color {
+        self.tile(rec.u, rec.v).sample_hit(rec)
+    }
+}
@@ -0,0 +1,54 @@
+use serde::Deserialize;
+
+use crate::color::{self, Color};
+
+/// A gradient mapping scalars to colors, which interpolates linearly between colors at sorted
+/// positions and extends the colors at both ends.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(from = "Vec<(f32, Color)>")]
+pub struct ColorRamp {
+    stops: Vec<(f32, Color)>,
+}
+
+impl Default for ColorRamp {
+    fn default() -> Self {
+        Self::linear(color::BLACK, color::WHITE)
+    }
+}
+
+impl From<Vec<(f32, Color)>> for ColorRamp {
+    fn from(stops: Vec<(f32, Color)>) -> Self {
+        Self::new(stops)
+    }
+}
+
+impl ColorRamp {
+    /// Create a ramp from pairs of position and color, in any order.
+    pub fn new<I>(stops: I) -> Self
+    where
+        I: IntoIterator<Item = (f32, Color)>,
+    {
+        let mut stops: Vec<_> = stops.into_iter().collect();
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self { stops }
+    }
+
+    /// Create a ramp from `start` at zero to `end` at one.
+    pub fn linear(start: Color, end: Color) -> Self {
+        Self::new([(0.0, start), (1.0, end)])
+    }
+
+    /// Get the color at position `t`, which is black if the ramp has no colors.
+    pub fn eval(&self, t: f32) -> Color {
+        let index = self.stops.partition_point(|&(position, _)| position <= t);
+        match (index.checked_sub(1), self.stops.get(index)) {
+            (Some(i), Some(&(end, high))) => {
+                let (start, low) = self.stops[i];
+                low.lerp(high, (t - start) / (end - start))
+            }
+            (Some(i), None) => self.stops[i].1,
+            (None, Some(&(_, high))) => high,
+            (None, None) => color::BLACK,
+        }
+    }
+}
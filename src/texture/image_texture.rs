@@ -1,14 +1,303 @@
 use std::path::Path;
+use std::sync::Arc;
 
+use glam::{Affine2, Vec2};
 use image::{DynamicImage, ImageReader};
-use palette::{LinSrgb, Srgb};
+use palette::Srgb;
+use serde::Deserialize;
 
 use crate::color::Color;
 use crate::math::Vec3;
+use crate::shape::HitRecord;
 use crate::texture::Texture;
 
+/// The reconstruction filter between texels.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Filter {
+    /// The nearest texel, which looks blocky in close-ups.
+    #[default]
+    Nearest,
+
+    /// The linear interpolation of the four nearest texels.
+    Bilinear,
+
+    /// The Catmull-Rom interpolation of the sixteen nearest texels, which is sharper than
+    /// bilinear.
+    Bicubic,
+}
+
+/// How texture coordinates outside [0, 1] are mapped into the image.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Wrap {
+    /// Tile the image.
+    Repeat,
+
+    /// Extend the texels on the edges.
+    #[default]
+    Clamp,
+
+    /// Tile the image flipped every other time.
+    Mirror,
+}
+
+impl Wrap {
+    /// Map the texel index `i` into an image of size `n`.
+    fn apply(self, i: i64, n: u32) -> u32 {
+        let n = i64::from(n);
+        let i = match self {
+            Self::Repeat => i.rem_euclid(n),
+            Self::Clamp => i.clamp(0, n - 1),
+            Self::Mirror => {
+                let i = i.rem_euclid(2 * n);
+                if i < n { i } else { 2 * n - 1 - i }
+            }
+        };
+        i as u32
+    }
+}
+
+/// How the footprint of a pixel is filtered from the mip pyramid of an image, which stops
+/// textures from aliasing in the distance.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Mipmap {
+    /// Ignore the footprint and look up the full resolution image.
+    #[default]
+    None,
+
+    /// Blend bilinear lookups in the two levels whose texels are closest in size to the
+    /// footprint, which blurs textures seen at grazing angles.
+    Trilinear,
+
+    /// Average the texels inside the elliptical footprint with Gaussian weights, which keeps
+    /// textures seen at grazing angles sharp.
+    Ewa,
+}
+
+/// The maximal ratio between the axes of the footprint filtered by `Mipmap::Ewa`, which bounds
+/// the number of texels averaged per lookup.
+const MAX_ANISOTROPY: f32 = 8.0;
+
+/// The falloff of the Gaussian weights of `Mipmap::Ewa`.
+const EWA_ALPHA: f32 = 2.0;
+
+/// How an image is sampled from texture coordinates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sampling {
+    /// The reconstruction filter.
+    pub filter: Filter,
+
+    /// The wrap mode along `u`.
+    pub wrap_u: Wrap,
+
+    /// The wrap mode along `v`.
+    pub wrap_v: Wrap,
+
+    /// The transformation applied to texture coordinates before the lookup.
+    pub transform: Affine2,
+
+    /// The filter of the pixel footprint.
+    pub mipmap: Mipmap,
+}
+
+impl Default for Sampling {
+    fn default() -> Self {
+        Self {
+            filter: Filter::default(),
+            wrap_u: Wrap::default(),
+            wrap_v: Wrap::default(),
+            transform: Affine2::IDENTITY,
+            mipmap: Mipmap::default(),
+        }
+    }
+}
+
+impl Sampling {
+    /// Sample the mip pyramid `levels` at texture coordinates `u` and `v`, over the footprint
+    /// spanned by the derivatives of the coordinates `duvdx` and `duvdy`. Texels are fetched by
+    /// `texel` from a level by column and row counted from the top.
+    pub(crate) fn lookup<F>(
+        &self,
+        levels: &[RtwImage],
+        u: f32,
+        v: f32,
+        duvdx: Vec2,
+        duvdy: Vec2,
+        texel: F,
+    ) -> Color
+    where
+        F: Fn(&RtwImage, u32, u32) -> Color,
+    {
+        let uv = self.transform.transform_point2(Vec2::new(u, v));
+        let duvdx = self.transform.transform_vector2(duvdx);
+        let duvdy = self.transform.transform_vector2(duvdy);
+
+        // Measure the footprint in texels of the full resolution image.
+        let base = Vec2::new(levels[0].width as f32, levels[0].height as f32);
+        let last = (levels.len() - 1) as f32;
+        match self.mipmap {
+            Mipmap::None => self.reconstruct(self.filter, &levels[0], uv, &texel),
+            Mipmap::Trilinear => {
+                let width = (duvdx * base).length().max((duvdy * base).length());
+                let lod = width.log2().clamp(0.0, last);
+                blend_levels(levels, lod, |level| {
+                    self.reconstruct(Filter::Bilinear, level, uv, &texel)
+                })
+            }
+            Mipmap::Ewa => {
+                let (mut major, mut minor) = (duvdx, duvdy);
+                if (major * base).length_squared() < (minor * base).length_squared() {
+                    (major, minor) = (minor, major);
+                }
+                let major_length = (major * base).length();
+                let mut minor_length = (minor * base).length();
+                if minor_length == 0.0 {
+                    return self.reconstruct(Filter::Bilinear, &levels[0], uv, &texel);
+                }
+
+                // Widen the minor axis of eccentric footprints, which would otherwise cover many
+                // texels of a fine level.
+                if minor_length * MAX_ANISOTROPY < major_length {
+                    let scale = major_length / (minor_length * MAX_ANISOTROPY);
+                    minor *= scale;
+                    minor_length *= scale;
+                }
+                let lod = minor_length.log2().clamp(0.0, last);
+                blend_levels(levels, lod, |level| {
+                    self.ewa(level, uv, major, minor, &texel)
+                })
+            }
+        }
+    }
+
+    /// Reconstruct the value of a level at texture coordinates `uv` from its texels.
+    fn reconstruct<F>(&self, filter: Filter, level: &RtwImage, uv: Vec2, texel: &F) -> Color
+    where
+        F: Fn(&RtwImage, u32, u32) -> Color,
+    {
+        let texel = |i: i64, j: i64| self.texel(level, i, j, texel);
+        let x = uv.x * level.width as f32;
+        let y = (1.0 - uv.y) * level.height as f32;
+        match filter {
+            Filter::Nearest => texel(x.floor() as i64, y.floor() as i64),
+            Filter::Bilinear => {
+                let (x, y) = (x - 0.5, y - 0.5);
+                let (i, j) = (x.floor() as i64, y.floor() as i64);
+                let (fx, fy) = (x - x.floor(), y - y.floor());
+                let top = texel(i, j) * (1.0 - fx) + texel(i + 1, j) * fx;
+                let bottom = texel(i, j + 1) * (1.0 - fx) + texel(i + 1, j + 1) * fx;
+                top * (1.0 - fy) + bottom * fy
+            }
+            Filter::Bicubic => {
+                let (x, y) = (x - 0.5, y - 0.5);
+                let (i, j) = (x.floor() as i64, y.floor() as i64);
+                let wx = catmull_rom(x - x.floor());
+                let wy = catmull_rom(y - y.floor());
+                let mut color = Color::ZERO;
+                for (dj, wy) in wy.iter().enumerate() {
+                    for (di, wx) in wx.iter().enumerate() {
+                        color += texel(i + di as i64 - 1, j + dj as i64 - 1) * wx * wy;
+                    }
+                }
+                // The negative lobes of the filter could overshoot below zero.
+                color.max(Color::ZERO)
+            }
+        }
+    }
+
+    /// Average the texels of a level inside the ellipse around texture coordinates `uv` whose
+    /// axes are `major` and `minor` in texture coordinates, with Gaussian weights.
+    fn ewa<F>(&self, level: &RtwImage, uv: Vec2, major: Vec2, minor: Vec2, texel: &F) -> Color
+    where
+        F: Fn(&RtwImage, u32, u32) -> Color,
+    {
+        // Work in texels whose centers are on integers, with rows counted from the top.
+        let size = Vec2::new(level.width as f32, -(level.height as f32));
+        let center = Vec2::new(uv.x, uv.y - 1.0) * size - 0.5;
+        let (d0, d1) = (major * size, minor * size);
+
+        // The ellipse `a * x^2 + b * x * y + c * y^2 < 1`, which is widened by one texel so that
+        // it always covers some texels.
+        let a = d0.y * d0.y + d1.y * d1.y + 1.0;
+        let b = -2.0 * (d0.x * d0.y + d1.x * d1.y);
+        let c = d0.x * d0.x + d1.x * d1.x + 1.0;
+        let f = a * c - 0.25 * b * b;
+        let (a, b, c) = (a / f, b / f, c / f);
+
+        // The bounding box of the ellipse.
+        let det = 4.0 * a * c - b * b;
+        let half_x = 2.0 * (det * c).sqrt() / det;
+        let half_y = 2.0 * (det * a).sqrt() / det;
+        let (x0, x1) = ((center.x - half_x).ceil(), (center.x + half_x).floor());
+        let (y0, y1) = ((center.y - half_y).ceil(), (center.y + half_y).floor());
+
+        let mut color = Color::ZERO;
+        let mut weights = 0.0;
+        for j in y0 as i64..=y1 as i64 {
+            let y = j as f32 - center.y;
+            for i in x0 as i64..=x1 as i64 {
+                let x = i as f32 - center.x;
+                let r2 = a * x * x + b * x * y + c * y * y;
+                if r2 < 1.0 {
+                    let weight = (-EWA_ALPHA * r2).exp() - (-EWA_ALPHA).exp();
+                    color += self.texel(level, i, j, texel) * weight;
+                    weights += weight;
+                }
+            }
+        }
+        if weights > 0.0 {
+            color / weights
+        } else {
+            self.reconstruct(Filter::Bilinear, level, uv, texel)
+        }
+    }
+
+    /// Fetch the texel of a level in column `i` and row `j` counted from the top, which are
+    /// wrapped into the level.
+    fn texel<F>(&self, level: &RtwImage, i: i64, j: i64, texel: &F) -> Color
+    where
+        F: Fn(&RtwImage, u32, u32) -> Color,
+    {
+        let i = self.wrap_u.apply(i, level.width);
+        let j = self.wrap_v.apply(j, level.height);
+        texel(level, i, j)
+    }
+}
+
+/// Blend the values looked up by `lookup` in the two levels around the fractional level `lod`.
+fn blend_levels<F>(levels: &[RtwImage], lod: f32, lookup: F) -> Color
+where
+    F: Fn(&RtwImage) -> Color,
+{
+    let i = lod.floor() as usize;
+    let t = lod - lod.floor();
+    match levels.get(i + 1) {
+        Some(next) if t > 0.0 => lookup(&levels[i]) * (1.0 - t) + lookup(next) * t,
+        _ => lookup(&levels[i]),
+    }
+}
+
+/// Get the Catmull-Rom weights of the four texels around the fractional position `t` between
+/// the second and the third.
+fn catmull_rom(t: f32) -> [f32; 4] {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    [
+        0.5 * (-t3 + 2.0 * t2 - t),
+        0.5 * (3.0 * t3 - 5.0 * t2 + 2.0),
+        0.5 * (-3.0 * t3 + 4.0 * t2 + t),
+        0.5 * (t3 - t2),
+    ]
+}
+
 pub struct ImageTexture {
-    image: RtwImage,
+    /// The mip pyramid of the image, from the full resolution down to one pixel.
+    levels: Arc<[RtwImage]>,
+
+    /// How the image is sampled.
+    pub sampling: Sampling,
 }
 
 impl ImageTexture {
@@ -17,9 +306,76 @@ impl ImageTexture {
         P: AsRef<Path>,
     {
         Ok(Self {
-            image: RtwImage::from_path(path)?,
+            levels: RtwImage::from_path(path)?.mip_pyramid(),
+            sampling: Sampling::default(),
         })
     }
+
+    /// Load an image holding non-color data such as normal maps, whose 8-bit values are kept as
+    /// they are instead of being decoded from sRGB.
+    pub fn load_data<P>(path: P) -> image::ImageResult<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(Self {
+            levels: RtwImage::from_path_data(path)?.mip_pyramid(),
+            sampling: Sampling::default(),
+        })
+    }
+
+    /// Get the alpha channel of the image as a gray texture, which is opaque if the image has no
+    /// alpha, e.g. to cut out an object with `Object::opacity`.
+    pub fn alpha(&self) -> ImageAlpha {
+        ImageAlpha {
+            levels: self.levels.clone(),
+            sampling: self.sampling,
+        }
+    }
+
+    /// Set the reconstruction filter between texels.
+    pub const fn filter(mut self, filter: Filter) -> Self {
+        self.sampling.filter = filter;
+        self
+    }
+
+    /// Set the wrap mode along both `u` and `v`.
+    pub const fn wrap(mut self, wrap: Wrap) -> Self {
+        self.sampling.wrap_u = wrap;
+        self.sampling.wrap_v = wrap;
+        self
+    }
+
+    /// Set the wrap modes along `u` and `v` respectively.
+    pub const fn wrap_uv(mut self, wrap_u: Wrap, wrap_v: Wrap) -> Self {
+        self.sampling.wrap_u = wrap_u;
+        self.sampling.wrap_v = wrap_v;
+        self
+    }
+
+    /// Set how the footprint of a pixel is filtered, which only applies to the intersections of
+    /// camera rays.
+    pub const fn mipmap(mut self, mipmap: Mipmap) -> Self {
+        self.sampling.mipmap = mipmap;
+        self
+    }
+
+    /// Set the transformation of texture coordinates, which are scaled, then rotated
+    /// counterclockwise by `rotation` in radians, then offset.
+    pub fn uv_transform(mut self, scale: Vec2, rotation: f32, offset: Vec2) -> Self {
+        self.sampling.transform = Affine2::from_scale_angle_translation(scale, rotation, offset);
+        self
+    }
+}
+
+impl ImageTexture {
+    /// Sample the image over the footprint spanned by the derivatives `duvdx` and `duvdy`.
+    fn lookup(&self, u: f32, v: f32, duvdx: Vec2, duvdy: Vec2) -> Color {
+        if self.levels[0].data.is_empty() {
+            return Color::new(0.0, 1.0, 1.0);
+        }
+        self.sampling
+            .lookup(&self.levels, u, v, duvdx, duvdy, RtwImage::pixel)
+    }
 }
 
 impl Texture for ImageTexture {
@@ -27,43 +383,99 @@ impl Texture for ImageTexture {
     /// Theta is the angle from pole -Y axis to +Y axis. And phi is the angle from -X axis to
     /// +Z axis and then to -X axis.
     fn sample(&self, u: f32, v: f32, _p: Vec3) -> Color {
-        self.image.sample(u, v)
+        self.lookup(u, v, Vec2::ZERO, Vec2::ZERO)
+    }
+
+    fn sample_hit(&self, rec: &HitRecord) -> Color {
+        let duvdx = Vec2::new(rec.dudx, rec.dvdx);
+        let duvdy = Vec2::new(rec.dudy, rec.dvdy);
+        self.lookup(rec.u, rec.v, duvdx, duvdy)
     }
 }
 
-struct RtwImage {
-    width: u32,
-    height: u32,
+/// The alpha channel of an image texture.
+pub struct ImageAlpha {
+    levels: Arc<[RtwImage]>,
+    sampling: Sampling,
+}
+
+impl ImageAlpha {
+    /// Sample the alpha over the footprint spanned by the derivatives `duvdx` and `duvdy`, which
+    /// is one if the image is opaque.
+    fn lookup(&self, u: f32, v: f32, duvdx: Vec2, duvdy: Vec2) -> Color {
+        if self.levels[0].alpha.is_empty() {
+            return Color::ONE;
+        }
+        self.sampling
+            .lookup(&self.levels, u, v, duvdx, duvdy, |level, i, j| {
+                Color::splat(level.pixel_alpha(i, j))
+            })
+    }
+}
+
+impl Texture for ImageAlpha {
+    fn sample(&self, u: f32, v: f32, _p: Vec3) -> Color {
+        self.lookup(u, v, Vec2::ZERO, Vec2::ZERO)
+    }
+
+    fn sample_hit(&self, rec: &HitRecord) -> Color {
+        let duvdx = Vec2::new(rec.dudx, rec.dvdx);
+        let duvdy = Vec2::new(rec.dudy, rec.dvdy);
+        self.lookup(rec.u, rec.v, duvdx, duvdy)
+    }
+}
+
+/// A decoded image whose pixels are stored as linear colors.
+pub(crate) struct RtwImage {
+    pub(crate) width: u32,
+    pub(crate) height: u32,
     data: Vec<Color>,
+
+    /// The alpha of each pixel, which is empty if the image is opaque.
+    alpha: Vec<f32>,
 }
 
 impl RtwImage {
-    fn from_path<P: AsRef<Path>>(path: P) -> image::ImageResult<Self> {
+    pub(crate) fn from_path<P: AsRef<Path>>(path: P) -> image::ImageResult<Self> {
+        let dyn_img = ImageReader::open(path)?.decode()?;
+        Self::from_dynamic(dyn_img, true)
+    }
+
+    /// Load an image whose 8-bit values are not decoded from sRGB.
+    pub(crate) fn from_path_data<P: AsRef<Path>>(path: P) -> image::ImageResult<Self> {
         let dyn_img = ImageReader::open(path)?.decode()?;
-        Self::from_dynamic(dyn_img)
+        Self::from_dynamic(dyn_img, false)
     }
 
-    fn from_dynamic(img: DynamicImage) -> image::ImageResult<Self> {
+    fn from_dynamic(img: DynamicImage, srgb: bool) -> image::ImageResult<Self> {
         match img {
             DynamicImage::ImageRgb32F(rgb) => Ok(Self::from_linear_rgb(rgb)),
             DynamicImage::ImageRgba32F(rgba) => Ok(Self::from_linear_rgba(rgba)),
             _ => {
-                let rgb = img.to_rgb8();
-                let (width, height) = rgb.dimensions();
+                let has_alpha = img.color().has_alpha();
+                let rgba = img.to_rgba8();
+                let (width, height) = rgba.dimensions();
                 let mut data = Vec::with_capacity((width * height) as usize);
+                let mut alpha = Vec::new();
 
-                for p in rgb.pixels() {
-                    let srgb: Srgb<u8> = Srgb::new(p[0], p[1], p[2]);
-                    let LinSrgb {
-                        red, green, blue, ..
-                    } = srgb.into_format::<f32>().into_linear();
+                for p in rgba.pixels() {
+                    let encoded: Srgb<f32> = Srgb::new(p[0], p[1], p[2]).into_format();
+                    let (red, green, blue) = if srgb {
+                        encoded.into_linear().into_components()
+                    } else {
+                        encoded.into_components()
+                    };
                     data.push(Color::new(red, green, blue));
+                    if has_alpha {
+                        alpha.push(p[3] as f32 / 255.0);
+                    }
                 }
 
                 Ok(Self {
                     width,
                     height,
                     data,
+                    alpha,
                 })
             }
         }
@@ -81,39 +493,75 @@ impl RtwImage {
             width,
             height,
             data,
+            alpha: Vec::new(),
         }
     }
 
     fn from_linear_rgba(rgba: image::Rgba32FImage) -> Self {
         let (width, height) = rgba.dimensions();
         let mut data = Vec::with_capacity((width * height) as usize);
+        let mut alpha = Vec::with_capacity((width * height) as usize);
 
         for p in rgba.pixels() {
-            data.push(Color::new(p[0], p[1], p[2])); // drop alpha
+            data.push(Color::new(p[0], p[1], p[2]));
+            alpha.push(p[3]);
         }
 
         Self {
             width,
             height,
             data,
+            alpha,
         }
     }
 
-    fn sample(&self, u: f32, v: f32) -> Color {
-        if self.data.is_empty() || self.width == 0 || self.height == 0 {
-            return Color::new(0.0, 1.0, 1.0);
-        }
+    /// Get the color of the pixel in column `i` and row `j` counted from the top.
+    pub(crate) fn pixel(&self, i: u32, j: u32) -> Color {
+        self.data[(j * self.width + i) as usize]
+    }
+
+    /// Get the alpha of the pixel in column `i` and row `j` counted from the top.
+    fn pixel_alpha(&self, i: u32, j: u32) -> f32 {
+        self.alpha[(j * self.width + i) as usize]
+    }
 
-        let u = u.clamp(0.0, 1.0);
-        let v = 1.0 - v.clamp(0.0, 1.0);
+    /// Get the image of half the size whose pixels average blocks of two by two pixels, where
+    /// the last row and column are repeated for odd sizes.
+    fn downsample(&self) -> Self {
+        let width = self.width.div_ceil(2);
+        let height = self.height.div_ceil(2);
+        let mut data = Vec::with_capacity((width * height) as usize);
+        let mut alpha = Vec::new();
+
+        for j in 0..height {
+            for i in 0..width {
+                let (i0, i1) = (2 * i, (2 * i + 1).min(self.width - 1));
+                let (j0, j1) = (2 * j, (2 * j + 1).min(self.height - 1));
+                let block = [(i0, j0), (i1, j0), (i0, j1), (i1, j1)];
+                data.push(block.iter().map(|&(i, j)| self.pixel(i, j)).sum::<Color>() / 4.0);
+                if !self.alpha.is_empty() {
+                    let sum: f32 = block.iter().map(|&(i, j)| self.pixel_alpha(i, j)).sum();
+                    alpha.push(sum / 4.0);
+                }
+            }
+        }
 
-        let i = (u * (self.width - 1) as f32) as u32;
-        let j = (v * (self.height - 1) as f32) as u32;
-        let idx = (j * self.width + i) as usize;
+        Self {
+            width,
+            height,
+            data,
+            alpha,
+        }
+    }
 
-        self.data
-            .get(idx)
-            .copied()
-            .unwrap_or_else(|| Color::new(0.0, 1.0, 1.0))
+    /// Build the mip pyramid of the image by halving it down to one pixel.
+    fn mip_pyramid(self) -> Arc<[RtwImage]> {
+        let mut levels = vec![self];
+        while let Some(last) = levels.last()
+            && (last.width > 1 || last.height > 1)
+        {
+            levels.push(last.downsample());
+        }
+        levels.into()
     }
 }
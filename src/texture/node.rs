@@ -0,0 +1,322 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use glam::{Affine2, Vec2};
+use serde::Deserialize;
+
+use crate::{
+    color::Color,
+    math::{Point3, Vec3},
+    shape::HitRecord,
+    texture::{
+        Texture,
+        color_ramp::ColorRamp,
+        image_texture::{Filter, ImageTexture, Mipmap, Wrap},
+        noise::Noise,
+        noise_texture::Fractal,
+    },
+};
+
+/// A node of a texture graph, which computes a value at an intersection from the values of its
+/// input nodes. Scalars are stored in all three channels and only the red channel is read where
+/// a scalar is expected, and coordinates are stored in the channels as `x`, `y` and `z`.
+///
+/// A graph is given by its output node, which implements `Texture`. In a scene file, each node is
+/// a table tagged by `type`, e.g.
+///
+/// ```toml
+/// type = "mix"
+/// a = { type = "constant", value = [0.8, 0.1, 0.1] }
+/// b = { type = "image", path = "wood.png", filter = "bilinear" }
+/// factor = { type = "noise", scale = 4.0 }
+/// ```
+#[derive(Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TextureNode {
+    /// A constant color.
+    Constant { value: Color },
+
+    /// An image looked up at the texture coordinates of the surface, or at the `x` and `y`
+    /// coordinates of the `uv` node.
+    Image(ImageNode),
+
+    /// Fractal noise in [0, 1] at the world-space position, or at the `position` node.
+    Noise {
+        /// The noise summed in layers.
+        #[serde(default)]
+        noise: Noise,
+
+        /// The frequency of the noise.
+        #[serde(default = "default_scale")]
+        scale: f32,
+
+        /// The number of layers of noise.
+        #[serde(default = "default_octaves")]
+        octaves: u32,
+
+        /// How the layers of noise are summed.
+        #[serde(default)]
+        fractal: Fractal,
+
+        /// The node of the position, which is the world-space position if it is `None`.
+        #[serde(default)]
+        position: Option<Box<TextureNode>>,
+    },
+
+    /// Tiles of two nodes alternating every `scale` units of the texture coordinates of the
+    /// surface, or of the `uv` node.
+    Checker {
+        scale: f32,
+        odd: Box<TextureNode>,
+        even: Box<TextureNode>,
+
+        #[serde(default)]
+        uv: Option<Box<TextureNode>>,
+    },
+
+    /// The sum of two nodes.
+    Add {
+        a: Box<TextureNode>,
+        b: Box<TextureNode>,
+    },
+
+    /// The product of two nodes.
+    Multiply {
+        a: Box<TextureNode>,
+        b: Box<TextureNode>,
+    },
+
+    /// The linear interpolation from `a` to `b` by the scalar `factor`.
+    Mix {
+        a: Box<TextureNode>,
+        b: Box<TextureNode>,
+        factor: Box<TextureNode>,
+    },
+
+    /// A node clamped into [`min`, `max`], which defaults to [0, 1].
+    Clamp {
+        input: Box<TextureNode>,
+
+        #[serde(default)]
+        min: f32,
+
+        #[serde(default = "default_max")]
+        max: f32,
+    },
+
+    /// A node raised to the power `exponent`, e.g. 2.2 for a gamma.
+    Power {
+        input: Box<TextureNode>,
+        exponent: f32,
+    },
+
+    /// The scalar `input` mapped through a color ramp.
+    Ramp {
+        input: Box<TextureNode>,
+        ramp: ColorRamp,
+    },
+
+    /// The `x` and `y` coordinates of a node scaled, then rotated counterclockwise by `rotation`
+    /// in radians, then offset.
+    UvTransform {
+        input: Box<TextureNode>,
+
+        #[serde(default = "default_uv_scale")]
+        scale: Vec2,
+
+        #[serde(default)]
+        rotation: f32,
+
+        #[serde(default)]
+        offset: Vec2,
+    },
+
+    /// The texture coordinates of the surface.
+    Uv,
+
+    /// The position in the space of the shape.
+    ObjectPosition,
+
+    /// The position in world space.
+    WorldPosition,
+
+    /// The shading normal in world space.
+    Normal,
+}
+
+const fn default_scale() -> f32 {
+    1.0
+}
+
+const fn default_max() -> f32 {
+    1.0
+}
+
+const fn default_octaves() -> u32 {
+    5
+}
+
+const fn default_uv_scale() -> Vec2 {
+    Vec2::ONE
+}
+
+impl TextureNode {
+    /// Evaluate the node at an intersection.
+    pub fn eval(&self, rec: &HitRecord) -> Color {
+        // Evaluate an optional coordinate node, which falls back to `default`.
+        let coordinates = |node: &Option<Box<Self>>, default: Vec3| {
+            node.as_ref().map_or(default, |node| node.eval(rec))
+        };
+
+        match self {
+            Self::Constant { value } => *value,
+            Self::Image(image) => match &image.uv {
+                Some(uv) => {
+                    let uv = uv.eval(rec);
+                    image.texture.sample(uv.x, uv.y, rec.p)
+                }
+                None => image.texture.sample_hit(rec),
+            },
+            Self::Noise {
+                noise,
+                scale,
+                octaves,
+                fractal,
+                position,
+            } => {
+                let p = *scale * coordinates(position, rec.p);
+                Color::splat(match fractal {
+                    Fractal::Fbm => 0.5 * (noise.fbm(p, *octaves) + 1.0),
+                    Fractal::Turbulence => noise.turbulence(p, *octaves),
+                })
+            }
+            Self::Checker {
+                scale,
+                odd,
+                even,
+                uv,
+            } => {
+                let uv = coordinates(uv, Vec3::new(rec.u, rec.v, 0.0)) / *scale;
+                if (uv.x.floor() as i32 + uv.y.floor() as i32) & 1 == 0 {
+                    even.eval(rec)
+                } else {
+                    odd.eval(rec)
+                }
+            }
+            Self::Add { a, b } => a.eval(rec) + b.eval(rec),
+            Self::Multiply { a, b } => a.eval(rec) * b.eval(rec),
+            Self::Mix { a, b, factor } => a.eval(rec).lerp(b.eval(rec), factor.eval(rec).x),
+            Self::Clamp { input, min, max } => input
+                .eval(rec)
+                .clamp(Color::splat(*min), Color::splat(*max)),
+            Self::Power { input, exponent } => input.eval(rec).max(Color::ZERO).powf(*exponent),
+            Self::Ramp { input, ramp } => ramp.eval(input.eval(rec).x),
+            Self::UvTransform {
+                input,
+                scale,
+                rotation,
+                offset,
+            } => {
+                let value = input.eval(rec);
+                let transform = Affine2::from_scale_angle_translation(*scale, *rotation, *offset);
+                let uv = transform.transform_point2(Vec2::new(value.x, value.y));
+                Color::new(uv.x, uv.y, value.z)
+            }
+            Self::Uv => Color::new(rec.u, rec.v, 0.0),
+            Self::ObjectPosition => rec.p_object,
+            Self::WorldPosition => rec.p,
+            Self::Normal => rec.shading_normal,
+        }
+    }
+}
+
+impl Texture for TextureNode {
+    /// Evaluate the graph at a point which is taken as both the world-space and the object-space
+    /// position, with no normal.
+    fn sample(&self, u: f32, v: f32, p: Point3) -> Color {
+        let rec = HitRecord {
+            p,
+            p_object: p,
+            u,
+            v,
+            ..Default::default()
+        };
+        self.eval(&rec)
+    }
+
+    fn sample_hit(&self, rec: &HitRecord) -> Color {
+        self.eval(rec)
+    }
+}
+
+/// An image node of a texture graph, which is loaded when the graph is read from a scene file.
+#[derive(Clone, Deserialize)]
+#[serde(try_from = "ImageDesc")]
+pub struct ImageNode {
+    /// The image texture.
+    pub texture: Arc<ImageTexture>,
+
+    /// The node of the texture coordinates, which are those of the surface if it is `None`.
+    pub uv: Option<Box<TextureNode>>,
+}
+
+impl ImageNode {
+    /// Create an image node looked up at the texture coordinates of the surface.
+    pub fn new(texture: ImageTexture) -> Self {
+        Self {
+            texture: Arc::new(texture),
+            uv: None,
+        }
+    }
+
+    /// Set the node of the texture coordinates.
+    pub fn uv(mut self, uv: TextureNode) -> Self {
+        self.uv = Some(Box::new(uv));
+        self
+    }
+}
+
+/// The description of an image node in a scene file.
+#[derive(Deserialize)]
+struct ImageDesc {
+    /// The path of the image file.
+    path: PathBuf,
+
+    /// Whether the image holds non-color data which is not decoded from sRGB.
+    #[serde(default)]
+    data: bool,
+
+    #[serde(default)]
+    filter: Filter,
+
+    #[serde(default)]
+    wrap: Wrap,
+
+    #[serde(default)]
+    mipmap: Mipmap,
+
+    #[serde(default)]
+    uv: Option<Box<TextureNode>>,
+}
+
+impl TryFrom<ImageDesc> for ImageNode {
+    type Error = String;
+
+    fn try_from(desc: ImageDesc) -> Result<Self, Self::Error> {
+        let texture = if desc.data {
+            ImageTexture::load_data(&desc.path)
+        } else {
+            ImageTexture::load(&desc.path)
+        }
+        .map_err(|err| format!("{}: {err}", desc.path.display()))?;
+        Ok(Self {
+            texture: Arc::new(
+                texture
+                    .filter(desc.filter)
+                    .wrap(desc.wrap)
+                    .mipmap(desc.mipmap),
+            ),
+            uv: desc.uv,
+        })
+    }
+}
@@ -0,0 +1,211 @@
+use serde::Deserialize;
+
+use crate::math::{Point3, Vec3};
+
+/// The kind of gradient noise.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NoiseKind {
+    /// Ken Perlin's improved noise interpolated over the corners of a cubic lattice.
+    #[default]
+    Perlin,
+
+    /// Simplex noise summed over the corners of a tetrahedral lattice, which is cheaper and has
+    /// fewer axis-aligned artifacts than Perlin noise.
+    Simplex,
+}
+
+/// A smooth pseudo-random function of 3D points, which is the building block of procedural
+/// textures. The same seed always gives the same noise.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct Noise {
+    /// The kind of gradient noise.
+    pub kind: NoiseKind,
+
+    /// The seed of the pseudo-random gradients.
+    pub seed: u32,
+}
+
+impl Noise {
+    /// Create Perlin noise from seed.
+    pub const fn perlin(seed: u32) -> Self {
+        Self {
+            kind: NoiseKind::Perlin,
+            seed,
+        }
+    }
+
+    /// Create simplex noise from seed.
+    pub const fn simplex(seed: u32) -> Self {
+        Self {
+            kind: NoiseKind::Simplex,
+            seed,
+        }
+    }
+
+    /// Get the noise at point `p`, which is roughly in [-1, 1] and varies over about one unit.
+    pub fn sample(&self, p: Point3) -> f32 {
+        match self.kind {
+            NoiseKind::Perlin => perlin(p, self.seed),
+            NoiseKind::Simplex => simplex(p, self.seed),
+        }
+    }
+
+    /// Get the fractional Brownian motion at point `p`, which sums `octaves` layers of noise of
+    /// doubling frequency and halving amplitude, normalized into roughly [-1, 1].
+    pub fn fbm(&self, p: Point3, octaves: u32) -> f32 {
+        self.octaves(p, octaves, |noise| noise)
+    }
+
+    /// Get the turbulence at point `p`, which sums the absolute values of `octaves` layers of
+    /// noise like `fbm`, normalized into [0, 1]. Its creases look like veins and flames.
+    pub fn turbulence(&self, p: Point3, octaves: u32) -> f32 {
+        self.octaves(p, octaves, f32::abs)
+    }
+
+    /// Sum `octaves` layers of noise mapped by `map`, normalized by the sum of amplitudes.
+    fn octaves<F>(&self, p: Point3, octaves: u32, map: F) -> f32
+    where
+        F: Fn(f32) -> f32,
+    {
+        let mut sum = 0.0;
+        let mut total = 0.0;
+        let mut amplitude = 1.0;
+        let mut p = p;
+        for octave in 0..octaves.max(1) {
+            // Decorrelate the octaves, whose lattices would otherwise align at the origin.
+            let noise = Self {
+                seed: self.seed.wrapping_add(octave),
+                ..*self
+            };
+            sum += amplitude * map(noise.sample(p));
+            total += amplitude;
+            amplitude *= 0.5;
+            p *= 2.0;
+        }
+        sum / total
+    }
+}
+
+/// Get the distances from point `p` to the nearest and the second nearest feature points of
+/// Worley noise, which scatters one feature point in each unit cell.
+pub fn worley(p: Point3, seed: u32) -> (f32, f32) {
+    let cell = p.floor();
+    let (mut f1, mut f2) = (f32::INFINITY, f32::INFINITY);
+    for dz in -1..=1 {
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let corner = cell + Vec3::new(dx as f32, dy as f32, dz as f32);
+                let (x, y, z) = (corner.x as i32, corner.y as i32, corner.z as i32);
+                let offset = Vec3::new(
+                    unit_float(hash(x, y, z, seed)),
+                    unit_float(hash(x, y, z, seed ^ 0x68e3_1da4)),
+                    unit_float(hash(x, y, z, seed ^ 0xb529_7a4d)),
+                );
+                let distance = (corner + offset - p).length();
+                if distance < f1 {
+                    (f1, f2) = (distance, f1);
+                } else if distance < f2 {
+                    f2 = distance;
+                }
+            }
+        }
+    }
+    (f1, f2)
+}
+
+/// Get Ken Perlin's improved noise at point `p`.
+fn perlin(p: Point3, seed: u32) -> f32 {
+    let cell = p.floor();
+    let (x, y, z) = (cell.x as i32, cell.y as i32, cell.z as i32);
+    let d = p - cell;
+    let fade = d * d * d * (d * (d * 6.0 - 15.0) + 10.0);
+
+    let corner = |i: i32, j: i32, k: i32| {
+        let offset = d - Vec3::new(i as f32, j as f32, k as f32);
+        gradient(hash(x + i, y + j, z + k, seed), offset)
+    };
+    let lerp = |a: f32, b: f32, t: f32| a + t * (b - a);
+    let x00 = lerp(corner(0, 0, 0), corner(1, 0, 0), fade.x);
+    let x10 = lerp(corner(0, 1, 0), corner(1, 1, 0), fade.x);
+    let x01 = lerp(corner(0, 0, 1), corner(1, 0, 1), fade.x);
+    let x11 = lerp(corner(0, 1, 1), corner(1, 1, 1), fade.x);
+    lerp(lerp(x00, x10, fade.y), lerp(x01, x11, fade.y), fade.z)
+}
+
+/// Get simplex noise at point `p`, following Stefan Gustavson's implementation.
+fn simplex(p: Point3, seed: u32) -> f32 {
+    const SKEW: f32 = 1.0 / 3.0;
+    const UNSKEW: f32 = 1.0 / 6.0;
+
+    // Find the cell of the skewed lattice and the offset from its origin corner.
+    let cell = (p + Vec3::splat(p.element_sum() * SKEW)).floor();
+    let (x, y, z) = (cell.x as i32, cell.y as i32, cell.z as i32);
+    let d0 = p - (cell - Vec3::splat(cell.element_sum() * UNSKEW));
+
+    // Find the tetrahedron of the cell by the order of the offsets along the axes.
+    let (first, second) = if d0.x >= d0.y {
+        if d0.y >= d0.z {
+            ((1, 0, 0), (1, 1, 0))
+        } else if d0.x >= d0.z {
+            ((1, 0, 0), (1, 0, 1))
+        } else {
+            ((0, 0, 1), (1, 0, 1))
+        }
+    } else if d0.y < d0.z {
+        ((0, 0, 1), (0, 1, 1))
+    } else if d0.x < d0.z {
+        ((0, 1, 0), (0, 1, 1))
+    } else {
+        ((0, 1, 0), (1, 1, 0))
+    };
+
+    let corner = |(i, j, k): (i32, i32, i32), n: f32| {
+        let offset = d0 - Vec3::new(i as f32, j as f32, k as f32) + Vec3::splat(n * UNSKEW);
+        let t = 0.6 - offset.length_squared();
+        if t > 0.0 {
+            t.powi(4) * gradient(hash(x + i, y + j, z + k, seed), offset)
+        } else {
+            0.0
+        }
+    };
+    32.0 * (corner((0, 0, 0), 0.0)
+        + corner(first, 1.0)
+        + corner(second, 2.0)
+        + corner((1, 1, 1), 3.0))
+}
+
+/// Get the dot product of the offset `d` from a lattice corner with one of the twelve gradients
+/// towards the edges of a cube, which is picked by `hash`.
+fn gradient(hash: u32, d: Vec3) -> f32 {
+    let h = hash & 15;
+    let u = if h < 8 { d.x } else { d.y };
+    let v = if h < 4 {
+        d.y
+    } else if h == 12 || h == 14 {
+        d.x
+    } else {
+        d.z
+    };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+/// Hash a lattice corner and a seed into pseudo-random bits.
+fn hash(x: i32, y: i32, z: i32, seed: u32) -> u32 {
+    let h = permute(seed ^ x as u32);
+    let h = permute(h ^ y as u32);
+    permute(h ^ z as u32)
+}
+
+/// Scramble the bits of an integer, following the PCG hash.
+fn permute(value: u32) -> u32 {
+    let state = value.wrapping_mul(747_796_405).wrapping_add(2_891_336_453);
+    let word = ((state >> ((state >> 28) + 4)) ^ state).wrapping_mul(277_803_737);
+    (word >> 22) ^ word
+}
+
+/// Map pseudo-random bits to a float in [0, 1).
+fn unit_float(bits: u32) -> f32 {
+    (bits >> 8) as f32 / (1 << 24) as f32
+}
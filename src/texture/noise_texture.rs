@@ -0,0 +1,308 @@
+use serde::Deserialize;
+
+use crate::{
+    color::Color,
+    math::{Point3, Vec3},
+    texture::{
+        Texture,
+        color_ramp::ColorRamp,
+        noise::{Noise, worley},
+    },
+};
+
+/// How the layers of noise are summed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Fractal {
+    /// Fractional Brownian motion, which looks like clouds.
+    #[default]
+    Fbm,
+
+    /// Turbulence, which looks like veins and flames.
+    Turbulence,
+}
+
+/// A texture of fractal noise in space mapped through a color ramp.
+#[derive(Clone, Debug)]
+pub struct NoiseTexture {
+    /// The noise summed in layers.
+    pub noise: Noise,
+
+    /// The frequency of the noise, where larger values give finer details.
+    pub scale: f32,
+
+    /// The number of layers of noise.
+    pub octaves: u32,
+
+    /// How the layers of noise are summed.
+    pub fractal: Fractal,
+
+    /// The colors of the noise from zero to one.
+    pub ramp: ColorRamp,
+}
+
+impl NoiseTexture {
+    /// Create a gray noise texture from frequency.
+    pub fn new(scale: f32) -> Self {
+        Self {
+            noise: Noise::default(),
+            scale,
+            octaves: 5,
+            fractal: Fractal::default(),
+            ramp: ColorRamp::default(),
+        }
+    }
+
+    /// Set the noise summed in layers.
+    pub const fn noise(mut self, noise: Noise) -> Self {
+        self.noise = noise;
+        self
+    }
+
+    /// Set the number of layers of noise.
+    pub const fn octaves(mut self, octaves: u32) -> Self {
+        self.octaves = octaves;
+        self
+    }
+
+    /// Set how the layers of noise are summed.
+    pub const fn fractal(mut self, fractal: Fractal) -> Self {
+        self.fractal = fractal;
+        self
+    }
+
+    /// Set the colors of the noise from zero to one.
+    pub fn ramp(mut self, ramp: ColorRamp) -> Self {
+        self.ramp = ramp;
+        self
+    }
+}
+
+impl Texture for NoiseTexture {
+    fn sample(&self, _u: f32, _v: f32, p: Point3) -> Color {
+        let p = self.scale * p;
+        let t = match self.fractal {
+            Fractal::Fbm => 0.5 * (self.noise.fbm(p, self.octaves) + 1.0),
+            Fractal::Turbulence => self.noise.turbulence(p, self.octaves),
+        };
+        self.ramp.eval(t)
+    }
+}
+
+/// A marble texture of parallel veins along the x axis, which are bent by turbulence.
+#[derive(Clone, Debug)]
+pub struct MarbleTexture {
+    /// The turbulence bending the veins.
+    pub noise: Noise,
+
+    /// The frequency of the veins and the turbulence.
+    pub scale: f32,
+
+    /// The number of layers of the turbulence.
+    pub octaves: u32,
+
+    /// How far the turbulence bends the veins.
+    pub distortion: f32,
+
+    /// The colors from the middle of the veins at zero to the stone between them at one.
+    pub ramp: ColorRamp,
+}
+
+impl MarbleTexture {
+    /// Create a white marble with gray veins from frequency.
+    pub fn new(scale: f32) -> Self {
+        Self {
+            noise: Noise::default(),
+            scale,
+            octaves: 7,
+            distortion: 10.0,
+            ramp: ColorRamp::new([
+                (0.0, Color::new(0.2, 0.2, 0.22)),
+                (0.3, Color::new(0.6, 0.6, 0.6)),
+                (0.6, Color::new(0.9, 0.89, 0.86)),
+            ]),
+        }
+    }
+
+    /// Set the turbulence bending the veins.
+    pub const fn noise(mut self, noise: Noise) -> Self {
+        self.noise = noise;
+        self
+    }
+
+    /// Set the number of layers of the turbulence.
+    pub const fn octaves(mut self, octaves: u32) -> Self {
+        self.octaves = octaves;
+        self
+    }
+
+    /// Set how far the turbulence bends the veins.
+    pub const fn distortion(mut self, distortion: f32) -> Self {
+        self.distortion = distortion;
+        self
+    }
+
+    /// Set the colors from the middle of the veins at zero to the stone between them at one.
+    pub fn ramp(mut self, ramp: ColorRamp) -> Self {
+        self.ramp = ramp;
+        self
+    }
+}
+
+impl Texture for MarbleTexture {
+    fn sample(&self, _u: f32, _v: f32, p: Point3) -> Color {
+        let p = self.scale * p;
+        let phase = p.x + self.distortion * self.noise.turbulence(p, self.octaves);
+        self.ramp.eval(phase.sin().abs())
+    }
+}
+
+/// A wood texture of growth rings around the y axis, which are warped by noise.
+#[derive(Clone, Debug)]
+pub struct WoodTexture {
+    /// The noise warping the rings.
+    pub noise: Noise,
+
+    /// The number of rings per unit length.
+    pub rings: f32,
+
+    /// The frequency of the noise warping the rings.
+    pub scale: f32,
+
+    /// The number of layers of the noise.
+    pub octaves: u32,
+
+    /// How far the noise warps the rings, in rings.
+    pub distortion: f32,
+
+    /// The colors across one ring, from the early wood at zero to the late wood at one.
+    pub ramp: ColorRamp,
+}
+
+impl WoodTexture {
+    /// Create a brown wood from the number of rings per unit length.
+    pub fn new(rings: f32) -> Self {
+        Self {
+            noise: Noise::default(),
+            rings,
+            scale: 1.0,
+            octaves: 3,
+            distortion: 0.5,
+            ramp: ColorRamp::new([
+                (0.0, Color::new(0.58, 0.36, 0.17)),
+                (0.7, Color::new(0.5, 0.3, 0.14)),
+                (1.0, Color::new(0.3, 0.16, 0.07)),
+            ]),
+        }
+    }
+
+    /// Set the noise warping the rings.
+    pub const fn noise(mut self, noise: Noise) -> Self {
+        self.noise = noise;
+        self
+    }
+
+    /// Set the frequency of the noise warping the rings.
+    pub const fn scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Set the number of layers of the noise.
+    pub const fn octaves(mut self, octaves: u32) -> Self {
+        self.octaves = octaves;
+        self
+    }
+
+    /// Set how far the noise warps the rings, in rings.
+    pub const fn distortion(mut self, distortion: f32) -> Self {
+        self.distortion = distortion;
+        self
+    }
+
+    /// Set the colors across one ring, from the early wood at zero to the late wood at one.
+    pub fn ramp(mut self, ramp: ColorRamp) -> Self {
+        self.ramp = ramp;
+        self
+    }
+}
+
+impl Texture for WoodTexture {
+    fn sample(&self, _u: f32, _v: f32, p: Point3) -> Color {
+        // Stretch the noise along the grain, which runs along the trunk.
+        let warp = self
+            .noise
+            .fbm(self.scale * p * Vec3::new(1.0, 0.1, 1.0), self.octaves);
+        let radius = Vec3::new(p.x, 0.0, p.z).length() * self.rings + self.distortion * warp;
+        self.ramp.eval(radius.rem_euclid(1.0))
+    }
+}
+
+/// The feature of Worley noise shaded by `CellularTexture`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Cellular {
+    /// The distance to the nearest feature point, which is zero at the center of each cell.
+    #[default]
+    Distance,
+
+    /// The difference between the distances to the two nearest feature points, which is zero
+    /// on the borders between cells like cracks, scales and stones.
+    Border,
+}
+
+/// A texture of cells around points scattered in space by Worley noise, mapped through a color
+/// ramp.
+#[derive(Clone, Debug)]
+pub struct CellularTexture {
+    /// The seed of the feature points.
+    pub seed: u32,
+
+    /// The number of cells per unit length.
+    pub scale: f32,
+
+    /// The feature shaded.
+    pub feature: Cellular,
+
+    /// The colors of the feature from zero to one.
+    pub ramp: ColorRamp,
+}
+
+impl CellularTexture {
+    /// Create a gray cellular texture from the number of cells per unit length.
+    pub fn new(scale: f32) -> Self {
+        Self {
+            seed: 0,
+            scale,
+            feature: Cellular::default(),
+            ramp: ColorRamp::default(),
+        }
+    }
+
+    /// Set the seed of the feature points.
+    pub const fn seed(mut self, seed: u32) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Set the feature shaded.
+    pub const fn feature(mut self, feature: Cellular) -> Self {
+        self.feature = feature;
+        self
+    }
+
+    /// Set the colors of the feature from zero to one.
+    pub fn ramp(mut self, ramp: ColorRamp) -> Self {
+        self.ramp = ramp;
+        self
+    }
+}
+
+impl Texture for CellularTexture {
+    fn sample(&self, _u: f32, _v: f32, p: Point3) -> Color {
+        let (f1, f2) = worley(self.scale * p, self.seed);
+        self.ramp.eval(match self.feature {
+            Cellular::Distance => f1,
+            Cellular::Border => f2 - f1,
+        })
+    }
+}
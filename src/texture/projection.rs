@@ -0,0 +1,185 @@
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use glam::{Mat3A, Mat4};
+
+use crate::{
+    color::Color,
+    math::{Point3, Vec3},
+    shape::{HitRecord, sphere::Sphere},
+    texture::Texture,
+};
+
+/// How a `Projection` computes texture coordinates from the position in its space.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mapping {
+    /// Project along the z axis, where `u` and `v` are the x and y coordinates.
+    Planar,
+
+    /// Project along each axis like `Planar` and blend the three lookups by the normal raised to
+    /// the power `sharpness`, where larger values give harder seams like a box.
+    Triplanar { sharpness: f32 },
+
+    /// Project from the origin onto a sphere, like the coordinates of `Sphere`.
+    Spherical,
+
+    /// Project from the y axis onto a cylinder, where `u` goes around the axis and `v` is the y
+    /// coordinate.
+    Cylindrical,
+}
+
+/// A texture looked up at coordinates projected from the hit position instead of the texture
+/// coordinates of the shape, so that shapes without texture coordinates can be textured. The
+/// position is taken in the space of the shape by default, so that the texture sticks to objects
+/// placed by `Transformed`.
+#[derive(Clone)]
+pub struct Projection {
+    /// The texture looked up at the projected coordinates.
+    pub tex: Arc<dyn Texture>,
+
+    /// How the coordinates are projected.
+    pub mapping: Mapping,
+
+    /// Whether the position is taken in world space instead of the space of the shape.
+    pub world_space: bool,
+
+    /// The transformation from the space of the shape or world space into the space of the
+    /// projection.
+    inverse_transform: Mat4,
+
+    /// The transformation of normals into the space of the projection.
+    normal_transform: Mat3A,
+}
+
+impl Projection {
+    /// Create a projection of the texture by mapping, placed at the origin of the space of the
+    /// shape.
+    pub fn new<T>(tex: T, mapping: Mapping) -> Self
+    where
+        T: Texture + 'static,
+    {
+        Self {
+            tex: Arc::new(tex),
+            mapping,
+            world_space: false,
+            inverse_transform: Mat4::IDENTITY,
+            normal_transform: Mat3A::IDENTITY,
+        }
+    }
+
+    /// Create a planar projection of the texture.
+    pub fn planar<T>(tex: T) -> Self
+    where
+        T: Texture + 'static,
+    {
+        Self::new(tex, Mapping::Planar)
+    }
+
+    /// Create a triplanar projection of the texture from blend sharpness.
+    pub fn triplanar<T>(tex: T, sharpness: f32) -> Self
+    where
+        T: Texture + 'static,
+    {
+        Self::new(tex, Mapping::Triplanar { sharpness })
+    }
+
+    /// Create a spherical projection of the texture.
+    pub fn spherical<T>(tex: T) -> Self
+    where
+        T: Texture + 'static,
+    {
+        Self::new(tex, Mapping::Spherical)
+    }
+
+    /// Create a cylindrical projection of the texture.
+    pub fn cylindrical<T>(tex: T) -> Self
+    where
+        T: Texture + 'static,
+    {
+        Self::new(tex, Mapping::Cylindrical)
+    }
+
+    /// Set the transformation from the space of the projection into the space of the shape or
+    /// world space, which places, orients and scales the projection like `Transformed` does for
+    /// shapes.
+    pub fn transform(mut self, transform: Mat4) -> Self {
+        self.inverse_transform = transform.inverse();
+        self.normal_transform = Mat3A::from_mat4(transform).transpose();
+        self
+    }
+
+    /// Set whether the position is taken in world space instead of the space of the shape, so
+    /// that objects move through a fixed texture.
+    pub const fn world_space(mut self, world_space: bool) -> Self {
+        self.world_space = world_space;
+        self
+    }
+
+    /// Look up the texture at the coordinates projected from the intersection, whose normal is
+    /// zero if unknown.
+    fn lookup(&self, rec: &HitRecord) -> Color {
+        let (p, normal) = if self.world_space {
+            (rec.p, rec.shading_normal)
+        } else {
+            (rec.p_object, rec.normal_object)
+        };
+        let local = self.inverse_transform.transform_point3a(p);
+
+        // Estimate the footprint of the pixel from the offsets of the point across it, which are
+        // only known in world space and ignore any scaling by `Transformed` for the shape space.
+        let offset = |du: f32, dv: f32| {
+            self.inverse_transform
+                .transform_vector3a(rec.dpdu * du + rec.dpdv * dv)
+        };
+        let (dpdx, dpdy) = (offset(rec.dudx, rec.dvdx), offset(rec.dudy, rec.dvdy));
+
+        // Forward the projected coordinates and their derivatives to the texture, where `u` of
+        // periodic projections wraps around.
+        let sample = |project: &dyn Fn(Point3) -> (f32, f32), periodic: bool| {
+            let (u, v) = project(local);
+            let derivatives = |dp: Vec3| {
+                let (u_offset, v_offset) = project(local + dp);
+                let du = u_offset - u;
+                (if periodic { du - du.round() } else { du }, v_offset - v)
+            };
+            let mut rec = rec.clone();
+            (rec.u, rec.v) = (u, v);
+            (rec.dudx, rec.dvdx) = derivatives(dpdx);
+            (rec.dudy, rec.dvdy) = derivatives(dpdy);
+            self.tex.sample_hit(&rec)
+        };
+
+        match self.mapping {
+            Mapping::Planar => sample(&|p| (p.x, p.y), false),
+            Mapping::Triplanar { sharpness } => {
+                // Blend the three projections evenly where the normal is unknown.
+                let normal = self.normal_transform.mul_vec3a(normal).normalize_or_zero();
+                let weights = if normal == Vec3::ZERO {
+                    Vec3::ONE
+                } else {
+                    normal.abs().powf(sharpness.max(1.0))
+                };
+                let weights = weights / weights.element_sum();
+                weights.x * sample(&|p| (p.z, p.y), false)
+                    + weights.y * sample(&|p| (p.x, p.z), false)
+                    + weights.z * sample(&|p| (p.x, p.y), false)
+            }
+            Mapping::Spherical => sample(&Sphere::get_sphere_uv, true),
+            Mapping::Cylindrical => sample(&|p| (((-p.z).atan2(p.x) + PI) / (2.0 * PI), p.y), true),
+        }
+    }
+}
+
+impl Texture for Projection {
+    fn sample(&self, _u: f32, _v: f32, p: Point3) -> Color {
+        self.lookup(&HitRecord {
+            p,
+            p_object: p,
+            ..Default::default()
+        })
+    }
+
+    fn sample_hit(&self, rec: &HitRecord) -> Color {
+        self.lookup(rec)
+    }
+}